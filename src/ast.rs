@@ -1,61 +1,246 @@
-use std::mem::{self, Discriminant};
+use std::{
+    collections::HashSet,
+    mem::{self, Discriminant},
+};
 
-use crate::token::Token;
+use crate::{
+    functions,
+    token::Token,
+    units::{self, Units},
+};
 
 #[derive(Debug)]
 pub enum Expression {
     Grouping(Box<Expression>),
-    Literal(f32),
+    Literal(f64),
     Variable(String),
     Assignment(Token, Box<Expression>),
     Unary(Token, Box<Expression>),
+    Postfix(Token, Box<Expression>),
     Binary(Token, Box<Expression>, Box<Expression>),
     SingleArity(Token, Box<Expression>),
     DoubleArity(Token, Box<Expression>, Box<Expression>),
     MultiArity(Token, Vec<Box<Expression>>),
+    Call(String, Vec<Box<Expression>>),
+    List(Vec<Box<Expression>>),
+    Index(Box<Expression>, Box<Expression>),
+    Slice(
+        Box<Expression>,
+        Option<Box<Expression>>,
+        Option<Box<Expression>>,
+    ),
+    /// A value with a unit made of names with exponents, e.g. `9.81 m/s^2`
+    Unit(Box<Expression>, Vec<(String, i32)>),
+    Convert(Box<Expression>, Vec<(String, i32)>),
+    /// `unit name = value`, or `unit name` for a new base unit
+    UnitDefinition(String, Option<Box<Expression>>),
+    /// A date with the seconds since midnight and a time zone. Without a
+    /// day it is today, without a zone in the time zone of the session.
+    DateTime(Option<i64>, f64, Option<i32>),
+    InZone(Box<Expression>, i32),
+    /// A duration written in several units, e.g. `3d 4h`
+    Compound(Box<Expression>, Box<Expression>),
+    /// A percentage of a value: `a + p%`, `a - p%`, `p% of a` or `p% off a`,
+    /// holding the value and the percentage without its `%`
+    Percentage(Token, Box<Expression>, Box<Expression>),
+    /// `x as %`
+    AsPercent(Box<Expression>),
+    /// A value with its standard uncertainty, `9.81 ± 0.02`
+    Uncertain(Box<Expression>, Box<Expression>),
+    /// The numbers between two bounds, `[1.0 .. 1.1]`
+    Interval(Box<Expression>, Box<Expression>),
+}
+
+fn unit_name(factors: &[(String, i32)]) -> String {
+    let factors: Vec<(&str, i32)> = factors.iter().map(|(n, e)| (n.as_str(), *e)).collect();
+    units::compose(&factors)
+}
+
+fn date_time(days: Option<i64>, seconds: f64, zone: Option<i32>) -> String {
+    let mut parts: Vec<String> = days
+        .map(|d| Token::Date(d).to_string())
+        .into_iter()
+        .collect();
+    if days.is_none() || seconds != 0.0 {
+        parts.push(Token::Time(seconds).to_string());
+    }
+    parts.extend(zone.map(|z| Token::Zone(z).to_string()));
+    parts.join(" ")
 }
 
 impl std::fmt::Display for Expression {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let s = match self {
-            Expression::Grouping(e) => format!("({})", e.to_string()),
+            Expression::Grouping(e) => format!("({})", e),
             Expression::Literal(n) => n.to_string(),
             Expression::Assignment(n, _) => n.get_identifier().unwrap(),
-            Expression::Unary(op, e) => format!("{}{}", op, e.to_string()),
+            Expression::Unary(op, e) => format!("{}{}", op, e),
+            Expression::Postfix(op, e) => format!("{}{}", e, op),
             Expression::Variable(s) => s.to_owned(),
-            Expression::Binary(op, l, r) => format!("{} {} {}", l.to_string(), op, r.to_string()),
-            Expression::SingleArity(op, a) => format!("{}({})", op, a.to_string()),
+            Expression::Binary(op, l, r) => format!("{} {} {}", l, op, r),
+            Expression::SingleArity(op, a) => format!("{}({})", op, a),
             Expression::DoubleArity(op, a, b) => {
-                format!("{}({},{})", op, a.to_string(), b.to_string())
+                format!("{}({},{})", op, a, b)
             }
             Expression::MultiArity(op, args) => {
-                let mut string = String::from(op.to_string());
+                let mut string = op.to_string();
                 string += "(";
                 string += &args[0].to_string();
-                for i in 1..args.len() {
+                for arg in args.iter().skip(1) {
                     string += ",";
-                    string += &args[i].to_string();
+                    string += &arg.to_string();
                 }
                 string += ")";
                 string
             }
+            Expression::Call(name, args) => {
+                let args: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+                format!("{}({})", name, args.join(","))
+            }
+            Expression::List(items) => {
+                let items: Vec<String> = items.iter().map(|i| i.to_string()).collect();
+                format!("[{}]", items.join(","))
+            }
+            Expression::Index(e, i) => format!("{}[{}]", e, i),
+            Expression::Slice(e, start, end) => {
+                let bound = |b: &Option<Box<Expression>>| b.as_ref().map(|b| b.to_string());
+                format!(
+                    "{}[{}:{}]",
+                    e,
+                    bound(start).unwrap_or_default(),
+                    bound(end).unwrap_or_default()
+                )
+            }
+            Expression::Unit(e, unit) => format!("{} {}", e, unit_name(unit)),
+            Expression::Convert(e, unit) => format!("{} to {}", e, unit_name(unit)),
+            Expression::UnitDefinition(name, None) => format!("unit {}", name),
+            Expression::UnitDefinition(name, Some(e)) => format!("unit {} = {}", name, e),
+            Expression::DateTime(days, seconds, zone) => date_time(*days, *seconds, *zone),
+            Expression::InZone(e, offset) => format!("{} to {}", e, Token::Zone(*offset)),
+            Expression::Compound(a, b) => format!("{} {}", a, b),
+            Expression::Percentage(op @ (Token::Of | Token::Off), a, p) => {
+                format!("{}% {} {}", p, op, a)
+            }
+            Expression::Percentage(op, a, p) => format!("{} {} {}%", a, op, p),
+            Expression::AsPercent(e) => format!("{} as %", e),
+            Expression::Uncertain(v, u) => format!("{} ± {}", v, u),
+            Expression::Interval(lo, hi) => format!("[{} .. {}]", lo, hi),
         };
         write!(f, "{}", s)
     }
 }
 
+impl Expression {
+    /// Renders the syntax tree with one node per line and the children of a
+    /// node indented below it
+    pub fn tree(&self) -> String {
+        let mut tree = String::new();
+        self.write_tree(&mut tree, "", "");
+        tree
+    }
+
+    fn write_tree(&self, tree: &mut String, first: &str, rest: &str) {
+        let label = match self {
+            Expression::Grouping(_) => "Grouping".to_string(),
+            Expression::Literal(n) => format!("Literal {}", n),
+            Expression::Variable(s) => format!("Variable {}", s),
+            Expression::Assignment(id, _) => format!("Assignment {}", id),
+            Expression::Unary(op, _) => format!("Unary {}", op),
+            Expression::Postfix(op, _) => format!("Postfix {}", op),
+            Expression::Binary(op, _, _) => format!("Binary {}", op),
+            Expression::SingleArity(op, _)
+            | Expression::DoubleArity(op, _, _)
+            | Expression::MultiArity(op, _) => format!("Function {}", op),
+            Expression::Call(name, _) => format!("Function {}", name),
+            Expression::List(_) => "List".to_string(),
+            Expression::Index(_, _) => "Index".to_string(),
+            Expression::Slice(_, _, _) => "Slice".to_string(),
+            Expression::Unit(_, unit) => format!("Unit {}", unit_name(unit)),
+            Expression::Convert(_, unit) => format!("Convert to {}", unit_name(unit)),
+            Expression::UnitDefinition(name, _) => format!("Unit definition {}", name),
+            Expression::DateTime(days, seconds, zone) => {
+                format!("Date {}", date_time(*days, *seconds, *zone))
+            }
+            Expression::InZone(_, offset) => format!("Convert to {}", Token::Zone(*offset)),
+            Expression::Compound(_, _) => "Compound".to_string(),
+            Expression::Percentage(op, _, _) => format!("Percentage {}", op),
+            Expression::AsPercent(_) => "As percentage".to_string(),
+            Expression::Uncertain(_, _) => "Uncertain".to_string(),
+            Expression::Interval(_, _) => "Interval".to_string(),
+        };
+        let children: Vec<&Expression> = match self {
+            Expression::Literal(_)
+            | Expression::Variable(_)
+            | Expression::DateTime(_, _, _)
+            | Expression::UnitDefinition(_, None) => vec![],
+            Expression::Grouping(e)
+            | Expression::Assignment(_, e)
+            | Expression::Unary(_, e)
+            | Expression::Postfix(_, e)
+            | Expression::SingleArity(_, e)
+            | Expression::Unit(e, _)
+            | Expression::Convert(e, _)
+            | Expression::InZone(e, _)
+            | Expression::AsPercent(e)
+            | Expression::UnitDefinition(_, Some(e)) => vec![e],
+            Expression::Binary(_, a, b)
+            | Expression::DoubleArity(_, a, b)
+            | Expression::Compound(a, b)
+            | Expression::Percentage(_, a, b)
+            | Expression::Uncertain(a, b)
+            | Expression::Interval(a, b) => vec![a, b],
+            Expression::MultiArity(_, args)
+            | Expression::Call(_, args)
+            | Expression::List(args) => args.iter().map(|a| a.as_ref()).collect(),
+            Expression::Index(e, i) => vec![e, i],
+            Expression::Slice(e, start, end) => [Some(e), start.as_ref(), end.as_ref()]
+                .into_iter()
+                .flatten()
+                .map(|e| e.as_ref())
+                .collect(),
+        };
+        *tree += first;
+        *tree += &label;
+        *tree += "\n";
+        for (i, child) in children.iter().enumerate() {
+            if i + 1 < children.len() {
+                child.write_tree(tree, &format!("{rest}├─ "), &format!("{rest}│  "));
+            } else {
+                child.write_tree(tree, &format!("{rest}└─ "), &format!("{rest}   "));
+            }
+        }
+    }
+}
+
 pub struct ASTParser {
     current: usize,
     tokens: Vec<Token>,
+    /// the units that can follow a value
+    units: Units,
+    /// the names of variables, which are never taken for units
+    variables: HashSet<String>,
 }
 
 impl ASTParser {
-    pub fn create_ast(tokens: Vec<Token>) -> Result<Box<Expression>, String> {
+    pub fn create_ast(
+        tokens: Vec<Token>,
+        units: &Units,
+        variables: HashSet<String>,
+    ) -> Result<Box<Expression>, String> {
         let mut parser = ASTParser {
             current: 0,
             tokens: tokens.clone(),
+            units: units.clone(),
+            variables,
         };
-        let expression = parser.expression();
+        let expression = parser.expression()?;
+        if let Some(Token::Identifier(name)) = parser.tokens.get(parser.current) {
+            if parser.current > 0
+                && parser.tokens.get(parser.current + 1) != Some(&Token::OpenParen)
+            {
+                return Err(parser.not_a_unit(name));
+            }
+        }
         if parser.current < parser.tokens.len() {
             let disjoint = tokens
                 .get(parser.current..parser.tokens.len())
@@ -67,20 +252,15 @@ impl ASTParser {
                 disjoint
             ));
         }
-        expression
+        Ok(expression)
     }
 
     fn expression(&mut self) -> Result<Box<Expression>, String> {
-        if self.r#match(&vec![Token::Let]) {
-            let id = self.peek().clone();
-            match id {
-                Token::Identifier(_) => {
-                    self.advance();
-                }
-                _ => {
-                    return Err("An identifier must come after the let keyword".to_string());
-                }
+        if self.r#match(&[Token::Let]) {
+            if !self.check(mem::discriminant(&Token::Identifier(Default::default()))) {
+                return Err("An identifier must come after the let keyword".to_string());
             }
+            let id = self.advance().clone();
 
             if self.check(mem::discriminant(&Token::Equal)) {
                 self.advance();
@@ -90,17 +270,56 @@ impl ASTParser {
                 );
             }
             let expr = self.expression();
-            return Ok(Box::new(Expression::Assignment(
-                id,
-                expr.expect("Unable to create syntax tree"),
-            )));
+            return Ok(Box::new(Expression::Assignment(id, expr?)));
         }
-        self.term()
+        if self.r#match(&[Token::Unit]) {
+            let name = match self.tokens.get(self.current) {
+                Some(Token::Identifier(name)) => name.clone(),
+                _ => return Err("A name must come after the unit keyword".to_string()),
+            };
+            self.advance();
+            let value = if self.r#match(&[Token::Equal]) {
+                Some(self.expression()?)
+            } else {
+                None
+            };
+            return Ok(Box::new(Expression::UnitDefinition(name, value)));
+        }
+        let mut expr = self.term()?;
+        // binds looser than arithmetic, `1 + 2 ± 0.1` is `(1 + 2) ± 0.1`
+        if self.r#match(&[Token::PlusMinus]) {
+            expr = Box::new(Expression::Uncertain(expr, self.term()?));
+        }
+        if self.r#match(&[Token::As]) {
+            if !self.r#match(&[Token::Percent]) {
+                return Err("Expected % after as".to_string());
+            }
+            return Ok(Box::new(Expression::AsPercent(expr)));
+        }
+        if self.r#match(&[Token::To, Token::In]) {
+            let keyword = self.previous().clone();
+            if let Some(Token::Zone(offset)) = self.tokens.get(self.current) {
+                let offset = *offset;
+                self.advance();
+                return Ok(Box::new(Expression::InZone(expr, offset)));
+            }
+            if !self.check_unit() {
+                if let Some(Token::Identifier(name)) = self.tokens.get(self.current) {
+                    return Err(format!(
+                        "Expected a unit after {keyword}, {}",
+                        self.not_a_unit(name)
+                    ));
+                }
+                return Err(format!("Expected a unit after {}", keyword));
+            }
+            return Ok(Box::new(Expression::Convert(expr, self.unit()?)));
+        }
+        Ok(expr)
     }
 
     fn function(&mut self) -> Result<Box<Expression>, String> {
         let operator = self.previous().clone();
-        if vec![
+        if [
             Token::Cos,
             Token::Sin,
             Token::Tan,
@@ -131,12 +350,9 @@ impl ASTParser {
                 ));
             }
 
-            return Ok(Box::new(Expression::SingleArity(
-                operator,
-                arg.expect("Unable to create syntax tree"),
-            )));
+            return Ok(Box::new(Expression::SingleArity(operator, arg?)));
         }
-        if vec![Token::Pow, Token::Log].contains(&operator) {
+        if [Token::Pow, Token::Log].contains(&operator) {
             if self.check(mem::discriminant(&Token::OpenParen)) {
                 self.advance();
             } else {
@@ -168,14 +384,10 @@ impl ASTParser {
                     operator
                 ));
             }
-            return Ok(Box::new(Expression::DoubleArity(
-                operator,
-                arg1.expect("Unable to create syntax tree"),
-                arg2.expect("Unable to create syntax tree"),
-            )));
+            return Ok(Box::new(Expression::DoubleArity(operator, arg1?, arg2?)));
         }
 
-        if vec![Token::Max, Token::Min].contains(&operator) {
+        if [Token::Max, Token::Min].contains(&operator) {
             if self.check(mem::discriminant(&Token::OpenParen)) {
                 self.advance();
             } else {
@@ -188,20 +400,20 @@ impl ASTParser {
             if self.end() {
                 return Err("Cannot end with opening parenthesis".to_string());
             }
-            while *self.peek() != Token::CloseParen {
-                args.push(self.expression().expect("Unable to create syntax tree"));
+            while !self.check(mem::discriminant(&Token::CloseParen)) {
+                args.push(self.expression()?);
 
-                if *self.peek() == Token::CloseParen {
+                if self.check(mem::discriminant(&Token::CloseParen)) {
                     break;
                 }
 
-                if *self.peek() != Token::Comma {
+                if !self.check(mem::discriminant(&Token::Comma)) {
                     return Err("Either missing comma or close parenthesis".to_string());
                 }
 
                 self.advance();
 
-                if *self.peek() == Token::CloseParen {
+                if self.check(mem::discriminant(&Token::CloseParen)) {
                     return Err("Trailing commas are not allowed".to_string());
                 }
             }
@@ -221,60 +433,258 @@ impl ASTParser {
         Err(format!("{} is not a valid function", operator))
     }
 
+    /// Parses the arguments of a function from the function registry
+    fn call(&mut self, name: String) -> Result<Box<Expression>, String> {
+        let function = functions::lookup(&name).ok_or(format!("{} is not a function", name))?;
+        self.advance();
+        let mut args: Vec<Box<Expression>> = Vec::new();
+        if !self.check(mem::discriminant(&Token::CloseParen)) {
+            loop {
+                args.push(self.expression()?);
+                if !self.r#match(&[Token::Comma]) {
+                    break;
+                }
+            }
+        }
+        if self.check(mem::discriminant(&Token::CloseParen)) {
+            self.advance();
+        } else {
+            return Err(format!("Missing closing parenthesis ')' after {}", name));
+        }
+        function.check_arity(args.len())?;
+        Ok(Box::new(Expression::Call(name, args)))
+    }
+
+    /// Adding or subtracting a percentage, as in `50 + 10%`, changes the
+    /// left side by that percentage of it. A percentage in parentheses or
+    /// combined with anything else is just a number, `50 + (10%)` is 50.1.
     fn term(&mut self) -> Result<Box<Expression>, String> {
         let mut expr = self.factor();
-        while self.r#match(&vec![Token::Minus, Token::Plus]) {
+        while self.r#match(&[Token::Minus, Token::Plus]) {
             let operator = self.previous().clone();
-            let right = self.factor();
-            expr = Ok(Box::new(Expression::Binary(
-                operator,
-                expr.expect("unable to create syntax tree"),
-                right.expect("unable to create syntax tree"),
-            )))
+            let right = self.factor()?;
+            expr = Ok(Box::new(match *right {
+                Expression::Postfix(Token::Percent, p) => {
+                    Expression::Percentage(operator, expr?, p)
+                }
+                right => Expression::Binary(operator, expr?, Box::new(right)),
+            }));
         }
         expr
     }
 
     fn factor(&mut self) -> Result<Box<Expression>, String> {
         let mut expr = self.unary();
-        while self.r#match(&vec![Token::Slash, Token::Star, Token::Percent]) {
+        while self.r#match(&[
+            Token::Slash,
+            Token::Star,
+            Token::Mod,
+            Token::At,
+            Token::Of,
+            Token::Off,
+        ]) {
             let operator = self.previous().clone();
             let right = self.unary();
-            expr = Ok(Box::new(Expression::Binary(
-                operator,
-                expr.expect("unable to create syntax tree"),
-                right.expect("unable to create syntax tree"),
-            )));
+            if [Token::Of, Token::Off].contains(&operator) {
+                let Expression::Postfix(Token::Percent, p) = *expr? else {
+                    return Err(format!("{} must follow a percentage like 20%", operator));
+                };
+                expr = Ok(Box::new(Expression::Percentage(operator, right?, p)));
+                continue;
+            }
+            expr = Ok(Box::new(Expression::Binary(operator, expr?, right?)));
         }
         expr
     }
 
     fn unary(&mut self) -> Result<Box<Expression>, String> {
-        if self.r#match(&vec![Token::Minus]) {
+        if self.r#match(&[Token::Minus]) {
             let operator = self.previous().clone();
             let right = self.unary();
-            return Ok(Box::new(Expression::Unary(
-                operator,
-                right.expect("unable to create syntax tree"),
-            )));
+            return Ok(Box::new(Expression::Unary(operator, right?)));
         }
-        self.primary()
+        self.power()
+    }
+
+    /// `a ^ b`, which binds tighter than a minus sign in front and groups
+    /// from the right
+    fn power(&mut self) -> Result<Box<Expression>, String> {
+        let expr = self.postfix()?;
+        if self.r#match(&[Token::Caret]) {
+            let operator = self.previous().clone();
+            let right = self.unary()?;
+            return Ok(Box::new(Expression::Binary(operator, expr, right)));
+        }
+        Ok(expr)
+    }
+
+    fn postfix(&mut self) -> Result<Box<Expression>, String> {
+        let mut expr = self.primary()?;
+        loop {
+            if self.r#match(&[Token::Percent]) {
+                // the remainder is `mod`, a value right after a percentage
+                // was most likely meant to be divided by
+                if matches!(
+                    self.tokens.get(self.current),
+                    Some(Token::Number(_) | Token::OpenParen | Token::OpenBracket)
+                ) || matches!(self.tokens.get(self.current), Some(Token::Identifier(_)))
+                    && !self.check_unit()
+                {
+                    return Err(
+                        "% is a percentage, use mod for the remainder, e.g. 7 mod 3".to_string()
+                    );
+                }
+                expr = Box::new(Expression::Postfix(Token::Percent, expr));
+            } else if self.r#match(&[Token::Deg, Token::Rad, Token::Grad, Token::Bang]) {
+                let operator = self.previous().clone();
+                expr = Box::new(Expression::Postfix(operator, expr));
+            } else if self.r#match(&[Token::OpenBracket]) {
+                expr = self.index(expr)?;
+            } else if self.check_unit() {
+                expr = Box::new(Expression::Unit(expr, self.unit()?));
+                // further values with units add up, as in `3d 4h`
+                while matches!(self.tokens.get(self.current), Some(Token::Number(_)))
+                    && self.is_unit(1)
+                {
+                    let value = Box::new(Expression::Literal(self.advance().get_number()?));
+                    let part = Box::new(Expression::Unit(value, self.unit()?));
+                    expr = Box::new(Expression::Compound(expr, part));
+                }
+            } else {
+                return Ok(expr);
+            }
+        }
+    }
+
+    /// Whether the token at `offset` from the current one names a unit
+    /// rather than calling a function
+    fn is_unit(&self, offset: usize) -> bool {
+        let name = match self.tokens.get(self.current + offset) {
+            Some(Token::Identifier(name)) => name.clone(),
+            Some(Token::Min) => Token::Min.to_string(),
+            _ => return false,
+        };
+        !self.variables.contains(&name)
+            && self.units.lookup(&name).is_some()
+            && self.tokens.get(self.current + offset + 1) != Some(&Token::OpenParen)
+    }
+
+    /// Explains why a name written where a unit can be is not one
+    fn not_a_unit(&self, name: &str) -> String {
+        if self.variables.contains(name) {
+            return format!("{name} is a variable, not a unit");
+        }
+        let currency_code = name.len() == 3 && name.chars().all(|c| c.is_ascii_uppercase());
+        if currency_code && self.units.rates().is_none() {
+            return format!(
+                "{name} is not a unit, and no exchange rates file is set for currencies (:set rates FILE)"
+            );
+        }
+        format!("{name} is not a unit")
+    }
+
+    fn check_unit(&self) -> bool {
+        self.is_unit(0)
+    }
+
+    /// Parses units like `kg m^2/s^2` into names with exponents. Units are
+    /// multiplied and divided from left to right, so only the unit right
+    /// after a `/` is divided by.
+    fn unit(&mut self) -> Result<Vec<(String, i32)>, String> {
+        let mut factors = Vec::new();
+        let mut sign = 1;
+        loop {
+            let name = self.advance().to_string();
+            let mut exponent = 1;
+            if self.r#match(&[Token::Caret]) {
+                let negative = self.r#match(&[Token::Minus]);
+                let n = match self.tokens.get(self.current) {
+                    Some(Token::Number(n)) if n.fract() == 0.0 => i32::try_from(*n as i64)
+                        .map_err(|_| format!("The exponent of {} is too large", name))?,
+                    _ => return Err(format!("The exponent of {} must be a whole number", name)),
+                };
+                self.advance();
+                exponent = if negative { -n } else { n };
+            }
+            factors.push((name, sign * exponent));
+            if self.check_unit() {
+                sign = 1;
+            } else if matches!(
+                self.tokens.get(self.current),
+                Some(Token::Star | Token::Slash)
+            ) && self.is_unit(1)
+            {
+                sign = if self.advance() == &Token::Slash {
+                    -1
+                } else {
+                    1
+                };
+            } else {
+                return Ok(factors);
+            }
+        }
+    }
+
+    /// Parses `[i]`, `[start:end]` or a slice with either bound left out
+    /// after a list
+    fn index(&mut self, list: Box<Expression>) -> Result<Box<Expression>, String> {
+        let start = if self.check(mem::discriminant(&Token::Colon)) {
+            None
+        } else {
+            Some(self.expression()?)
+        };
+        let expr = if self.r#match(&[Token::Colon]) {
+            let end = if self.check(mem::discriminant(&Token::CloseBracket)) {
+                None
+            } else {
+                Some(self.expression()?)
+            };
+            Expression::Slice(list, start, end)
+        } else {
+            // start is only None when a colon follows
+            Expression::Index(list, start.unwrap())
+        };
+        if !self.r#match(&[Token::CloseBracket]) {
+            return Err("Missing closing bracket ']'".to_string());
+        }
+        Ok(Box::new(expr))
     }
 
     fn primary(&mut self) -> Result<Box<Expression>, String> {
-        if self.r#match(&vec![Token::OpenParen]) {
+        if self.r#match(&[Token::OpenParen]) {
             let expr = self.expression();
             if self.check(mem::discriminant(&Token::CloseParen)) {
                 self.advance();
             } else {
                 return Err("Missing matching parenthesis ')'".to_string());
             }
-            return Ok(Box::new(Expression::Grouping(
-                expr.expect("Unable to create syntax tree"),
-            )));
+            return Ok(Box::new(Expression::Grouping(expr?)));
         }
 
-        if self.r#match(&vec![
+        if self.r#match(&[Token::OpenBracket]) {
+            let mut items: Vec<Box<Expression>> = Vec::new();
+            if !self.check(mem::discriminant(&Token::CloseBracket)) {
+                loop {
+                    items.push(self.expression()?);
+                    if items.len() == 1 && self.r#match(&[Token::DotDot]) {
+                        let hi = self.expression()?;
+                        if !self.r#match(&[Token::CloseBracket]) {
+                            return Err("An interval is written [lo .. hi]".to_string());
+                        }
+                        return Ok(Box::new(Expression::Interval(items.remove(0), hi)));
+                    }
+                    if !self.r#match(&[Token::Comma]) {
+                        break;
+                    }
+                }
+            }
+            if !self.r#match(&[Token::CloseBracket]) {
+                return Err("Missing closing bracket ']'".to_string());
+            }
+            return Ok(Box::new(Expression::List(items)));
+        }
+
+        if self.r#match(&[
             Token::Cos,
             Token::Tan,
             Token::Sin,
@@ -289,21 +699,42 @@ impl ASTParser {
             return self.function();
         }
 
-        if self.r#match(&vec![Token::Number(Default::default())]) {
+        if self.r#match(&[Token::Number(Default::default())]) {
             return Ok(Box::new(Expression::Literal(self.previous().get_number()?)));
         }
 
-        if self.r#match(&vec![Token::Identifier(Default::default())]) {
-            return Ok(Box::new(Expression::Variable(
-                self.previous().get_identifier()?,
-            )));
+        if let Some(&(Token::Date(_) | Token::Time(_))) = self.tokens.get(self.current) {
+            let mut days = None;
+            if let Some(Token::Date(d)) = self.tokens.get(self.current) {
+                days = Some(*d);
+                self.advance();
+            }
+            let mut seconds = 0.0;
+            if let Some(Token::Time(s)) = self.tokens.get(self.current) {
+                seconds = *s;
+                self.advance();
+            }
+            let mut zone = None;
+            if let Some(Token::Zone(offset)) = self.tokens.get(self.current) {
+                zone = Some(*offset);
+                self.advance();
+            }
+            return Ok(Box::new(Expression::DateTime(days, seconds, zone)));
+        }
+
+        if self.r#match(&[Token::Identifier(Default::default())]) {
+            let name = self.previous().get_identifier()?;
+            if self.check(mem::discriminant(&Token::OpenParen)) {
+                return self.call(name);
+            }
+            return Ok(Box::new(Expression::Variable(name)));
         }
 
         Err("Unable to create syntax tree".to_string())
     }
 
     /// checks if current token matches given token and consumes it if it does
-    fn r#match(&mut self, types: &Vec<Token>) -> bool {
+    fn r#match(&mut self, types: &[Token]) -> bool {
         for t in types.iter() {
             if self.check(mem::discriminant(t)) {
                 self.advance();
@@ -0,0 +1,237 @@
+use std::path::Path;
+
+use crate::{
+    ast::ASTParser,
+    constants, functions,
+    scanner::tokenize,
+    session::Session,
+    settings::Settings,
+    units::{self, Dimension, Quantity, Unit},
+    value::Value,
+};
+
+/// REPL meta-commands, all starting with a colon, and their descriptions
+pub const COMMANDS: &[(&str, &str)] = &[
+    (":vars", "list variables and their values"),
+    (":funcs", "list functions and their signatures"),
+    (
+        ":consts",
+        "list constants with their units and uncertainties",
+    ),
+    (":units", "list units, those marked with * take SI prefixes"),
+    (":del NAME", "delete a variable"),
+    (":clear", "delete all variables and previous results"),
+    (
+        ":save FILE",
+        "save variables and previous results to a file",
+    ),
+    (":load FILE", "replace the session with one saved to a file"),
+    (
+        ":help [NAME]",
+        "show this help or describe a function or constant",
+    ),
+    (":ast FORMULA", "show the syntax tree of a formula"),
+];
+
+pub fn is_command(line: &str) -> bool {
+    line.starts_with(':')
+}
+
+/// Whether a line asks to leave the REPL
+pub fn is_exit(line: &str) -> bool {
+    matches!(line.trim(), "exit" | "quit")
+}
+
+/// Runs a meta-command and returns the text to show for it
+pub fn run_command(line: &str, session: &mut Session) -> Result<String, String> {
+    let line = line.trim_start_matches(':').trim();
+    let (name, arg) = match line.split_once(char::is_whitespace) {
+        Some((name, arg)) => (name, arg.trim()),
+        None => (line, ""),
+    };
+    match (name, arg) {
+        ("vars", "") => {
+            let mut variables: Vec<(&String, &Value)> = session.variables().iter().collect();
+            if variables.is_empty() {
+                return Ok("No variables defined".to_string());
+            }
+            variables.sort_by(|a, b| a.0.cmp(b.0));
+            Ok(variables
+                .iter()
+                .map(|(name, value)| format!("{name} = {value}"))
+                .collect::<Vec<String>>()
+                .join("\n"))
+        }
+        ("funcs", "") => Ok(functions::FUNCTIONS
+            .iter()
+            .map(|f| format!("{:<20}{}", f.signature(), f.description))
+            .collect::<Vec<String>>()
+            .join("\n")),
+        ("consts", "") => Ok(constants::CONSTANTS
+            .iter()
+            .map(|c| format!("{:<44}{}", c.to_string(), c.description))
+            .collect::<Vec<String>>()
+            .join("\n")),
+        ("units", "") => {
+            let built_in = units::UNITS.iter().map(|u| {
+                let name = format!("{}{}", u.name, if u.prefixes { "*" } else { "" });
+                format!("{:<10}{}", name, u.description)
+            });
+            // units defined in the session all take prefixes
+            let custom = session.units().custom().iter().map(|u| {
+                let definition = if u.dimension == Dimension::new(&[(&u.name, 1)]) {
+                    "new base unit".to_string()
+                } else {
+                    let value = Value::Quantity(Quantity {
+                        value: u.factor,
+                        unit: Unit::si(u.dimension.clone()),
+                    });
+                    session.settings().format_value(&value)
+                };
+                format!("{:<10}{}", format!("{}*", u.name), definition)
+            });
+            // currencies are listed with their value in the base currency
+            let currencies = session.units().rates().into_iter().flat_map(|rates| {
+                rates.currencies.iter().map(|c| {
+                    let value = Value::Quantity(Quantity {
+                        value: c.factor,
+                        unit: Unit::si(c.dimension.clone()),
+                    });
+                    format!("{:<10}{}", c.name, session.settings().format_value(&value))
+                })
+            });
+            let listing: Vec<String> = built_in.chain(custom).chain(currencies).collect();
+            Ok(listing.join("\n"))
+        }
+        ("del", "") => Err("Usage: :del NAME".to_string()),
+        ("del", name) => {
+            session.remove(name)?;
+            Ok(format!("Deleted {name}"))
+        }
+        ("clear", "") => {
+            session.clear();
+            Ok("Deleted all variables and previous results".to_string())
+        }
+        ("set", "") => {
+            let settings = session.settings();
+            let mut listing = Vec::new();
+            for (key, description) in Settings::KEYS {
+                let value = settings.get(key).unwrap_or_default();
+                listing.push(format!("{:<23} {description}", format!("{key} = {value}")));
+            }
+            Ok(listing.join("\n"))
+        }
+        ("set", setting) => {
+            let (key, value) = setting
+                .split_once(char::is_whitespace)
+                .ok_or("Usage: :set KEY VALUE".to_string())?;
+            let previous = session.settings().clone();
+            session.settings_mut().set(key, value)?;
+            if key == "seed" {
+                session.reseed();
+            }
+            if key == "rates" {
+                if let Err(e) = session.refresh_rates() {
+                    *session.settings_mut() = previous;
+                    session.refresh_rates()?;
+                    return Err(e);
+                }
+            }
+            Ok(format!("{key} = {}", value.trim()))
+        }
+        ("save", "") => Err("Usage: :save FILE".to_string()),
+        ("save", file) => {
+            session.save(Path::new(file))?;
+            Ok(format!("Saved session to {file}"))
+        }
+        ("load", "") => Err("Usage: :load FILE".to_string()),
+        ("load", file) => {
+            *session = Session::load(Path::new(file))?;
+            Ok(format!("Loaded session from {file}"))
+        }
+        ("help", "") => {
+            let mut help = String::from("Type a formula to calculate it, or one of:");
+            for (usage, description) in COMMANDS {
+                help += &format!("\n  {usage:<20}{description}");
+            }
+            Ok(help)
+        }
+        ("help", name) => {
+            if let Some(f) = functions::lookup(name) {
+                return Ok(format!("{}\n  {}", f.signature(), f.description));
+            }
+            match constants::lookup(name) {
+                Some(c) => Ok(format!("{}\n  {}", c, c.description)),
+                None => Err(format!("{name} is not a function or constant")),
+            }
+        }
+        ("ast", "") => Err("Usage: :ast FORMULA".to_string()),
+        ("ast", formula) => {
+            let variables = session.variables().keys().cloned().collect();
+            let tokens = tokenize(formula.to_string())?;
+            let ast = ASTParser::create_ast(tokens, session.units(), variables)?;
+            Ok(ast.tree().trim_end().to_string())
+        }
+        _ => Err(format!(
+            "Unknown command :{line}, type :help for a list of commands"
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_exit, run_command};
+    use crate::session::Session;
+
+    #[test]
+    fn recognises_exit() {
+        assert!(is_exit("exit"));
+        assert!(is_exit("  quit "));
+        assert!(!is_exit("exit()"));
+        assert!(!is_exit("let quit = 1"));
+    }
+
+    #[test]
+    fn manages_variables() {
+        let mut session = Session::new();
+        assert_eq!(
+            run_command(":vars", &mut session).unwrap(),
+            "No variables defined"
+        );
+        session.run("let b = 2").unwrap();
+        session.run("let a = 1").unwrap();
+        session.run("a + b").unwrap();
+        assert_eq!(run_command(":vars", &mut session).unwrap(), "a = 1\nb = 2");
+        assert!(run_command(":del a", &mut session).is_ok());
+        assert!(run_command(":del a", &mut session).is_err());
+        assert_eq!(run_command(":vars", &mut session).unwrap(), "b = 2");
+        run_command(":clear", &mut session).unwrap();
+        assert!(session.variables().is_empty());
+        assert!(session.get("ans").is_err());
+    }
+
+    #[test]
+    fn describes_formulas() {
+        let mut session = Session::new();
+        assert_eq!(
+            run_command(":ast 1 + max(2, a) * 3", &mut session).unwrap(),
+            "Binary +\n\
+             ├─ Literal 1\n\
+             └─ Binary *\n   \
+                ├─ Function max\n   \
+                │  ├─ Literal 2\n   \
+                │  └─ Variable a\n   \
+                └─ Literal 3"
+        );
+        assert_eq!(
+            run_command(":help pow", &mut session).unwrap(),
+            "pow(base, exp)\n  base raised to the power of exp"
+        );
+        assert_eq!(
+            run_command(":help G", &mut session).unwrap(),
+            "G = 6.6743e-11 ± 1.5e-15 m^3 kg^-1 s^-2\n  Newtonian constant of gravitation"
+        );
+        assert!(run_command(":help nope", &mut session).is_err());
+        assert!(run_command(":nope", &mut session).is_err());
+    }
+}
@@ -0,0 +1,149 @@
+use crate::{
+    commands::COMMANDS, constants::CONSTANTS, editor::Candidate, functions::FUNCTIONS,
+    session::Session, value::Value,
+};
+
+const KEYWORDS: &[&str] = &["let", "unit", "to", "in", "mod", "of", "off", "as"];
+
+/// Completes built-in functions, keywords, constants and the session's
+/// variables, as well as meta-commands
+pub struct Completer<'a> {
+    session: &'a Session,
+}
+
+impl<'a> Completer<'a> {
+    pub fn new(session: &'a Session) -> Completer<'a> {
+        Completer { session }
+    }
+
+    /// Returns the byte offset in `line` where the word before the cursor
+    /// starts and the candidates completing it
+    pub fn complete(&self, line: &str) -> (usize, Vec<Candidate>) {
+        if line.starts_with(':') && !line.contains(char::is_whitespace) {
+            let candidates = COMMANDS
+                .iter()
+                .filter(|(usage, _)| usage.starts_with(line))
+                .map(|(usage, description)| {
+                    let name = usage.split(' ').next().unwrap_or(usage);
+                    Candidate {
+                        replacement: format!("{name} "),
+                        display: name.to_string(),
+                        hint: Some(format!("{usage}  {description}")),
+                    }
+                })
+                .collect();
+            return (0, candidates);
+        }
+        // the argument of :help is a bare function or constant name
+        if let Some(prefix) = line.strip_prefix(":help ") {
+            let start = line.len() - prefix.trim_start().len();
+            let candidates = FUNCTIONS
+                .iter()
+                .map(|f| f.name)
+                .chain(CONSTANTS.iter().map(|c| c.name))
+                .filter(|name| name.starts_with(&line[start..]))
+                .map(|name| Candidate {
+                    replacement: name.to_string(),
+                    display: name.to_string(),
+                    hint: None,
+                })
+                .collect();
+            return (start, candidates);
+        }
+
+        let start = line
+            .char_indices()
+            .rev()
+            .take_while(|(_, c)| c.is_ascii_alphanumeric() || *c == '_')
+            .last()
+            .map_or(line.len(), |(i, _)| i);
+        let prefix = &line[start..];
+        // numbers are not identifiers
+        if prefix.is_empty() || prefix.starts_with(|c: char| c.is_ascii_digit()) {
+            return (start, Vec::new());
+        }
+
+        let mut candidates: Vec<Candidate> = FUNCTIONS
+            .iter()
+            .filter(|f| f.name.starts_with(prefix))
+            .map(|f| Candidate {
+                replacement: format!("{}(", f.name),
+                display: f.signature(),
+                hint: Some(f.signature()),
+            })
+            .collect();
+        candidates.extend(
+            KEYWORDS
+                .iter()
+                .filter(|k| k.starts_with(prefix))
+                .map(|k| Candidate {
+                    replacement: format!("{k} "),
+                    display: k.to_string(),
+                    hint: None,
+                }),
+        );
+        let mut variables: Vec<(&str, Value)> = self
+            .session
+            .variables()
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.clone()))
+            .chain(self.session.get("ans").ok().map(|value| ("ans", value)))
+            .filter(|(name, _)| name.starts_with(prefix))
+            .collect();
+        variables.sort_by(|a, b| a.0.cmp(b.0));
+        candidates.extend(variables.into_iter().map(|(name, value)| Candidate {
+            replacement: name.to_string(),
+            display: format!("{name} = {value}"),
+            hint: None,
+        }));
+        candidates.extend(
+            CONSTANTS
+                .iter()
+                .filter(|c| c.name.starts_with(prefix))
+                .map(|c| Candidate {
+                    replacement: c.name.to_string(),
+                    display: c.to_string(),
+                    hint: Some(format!("{}: {}", c, c.description)),
+                }),
+        );
+        (start, candidates)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Completer;
+    use crate::session::Session;
+
+    #[test]
+    fn completes_functions_and_variables() {
+        let mut session = Session::new();
+        session.run("let mass = 2").unwrap();
+        session.run("let speed = 3").unwrap();
+        let completer = Completer::new(&session);
+
+        let (start, candidates) = completer.complete("1 + pow");
+        assert_eq!(start, 4);
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(candidates[0].replacement, "pow(");
+        assert_eq!(candidates[0].display, "pow(base, exp)");
+
+        let (start, candidates) = completer.complete("max(1,ma");
+        assert_eq!(start, 6);
+        let names: Vec<&str> = candidates.iter().map(|c| c.replacement.as_str()).collect();
+        assert_eq!(names, ["max(", "mass"]);
+        let (_, candidates) = completer.complete("m_");
+        let names: Vec<&str> = candidates.iter().map(|c| c.replacement.as_str()).collect();
+        assert_eq!(names, ["m_e", "m_p"]);
+
+        let (_, candidates) = completer.complete("2 * 1");
+        assert!(candidates.is_empty());
+
+        let (start, candidates) = completer.complete(":he");
+        assert_eq!(start, 0);
+        assert_eq!(candidates[0].replacement, ":help ");
+        let (start, candidates) = completer.complete(":help fl");
+        assert_eq!(start, 6);
+        assert_eq!(candidates[0].replacement, "floor");
+    }
+}
@@ -0,0 +1,151 @@
+/// A read-only named value. Physical constants are given in SI units with
+/// their CODATA 2018 standard uncertainty, 0 for exact values.
+#[derive(Debug, PartialEq)]
+pub struct Constant {
+    pub name: &'static str,
+    pub value: f64,
+    pub unit: &'static str,
+    pub uncertainty: f64,
+    pub description: &'static str,
+}
+
+impl std::fmt::Display for Constant {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} = {}", self.name, number(self.value))?;
+        if self.uncertainty != 0.0 {
+            write!(f, " ± {}", number(self.uncertainty))?;
+        }
+        if !self.unit.is_empty() {
+            write!(f, " {}", self.unit)?;
+        }
+        Ok(())
+    }
+}
+
+/// Very small and very large values in scientific notation
+fn number(value: f64) -> String {
+    if value != 0.0 && !(1e-4..1e9).contains(&value.abs()) {
+        format!("{:e}", value)
+    } else {
+        value.to_string()
+    }
+}
+
+pub const CONSTANTS: &[Constant] = &[
+    Constant {
+        name: "pi",
+        value: std::f64::consts::PI,
+        unit: "",
+        uncertainty: 0.0,
+        description: "ratio of a circle's circumference to its diameter",
+    },
+    Constant {
+        name: "tau",
+        value: std::f64::consts::TAU,
+        unit: "",
+        uncertainty: 0.0,
+        description: "ratio of a circle's circumference to its radius, 2 pi",
+    },
+    Constant {
+        name: "e",
+        value: std::f64::consts::E,
+        unit: "",
+        uncertainty: 0.0,
+        description: "Euler's number, the base of the natural logarithm",
+    },
+    Constant {
+        name: "phi",
+        value: 1.618033988749895,
+        unit: "",
+        uncertainty: 0.0,
+        description: "golden ratio, (1 + sqrt(5)) / 2",
+    },
+    Constant {
+        name: "c",
+        value: 299792458.0,
+        unit: "m/s",
+        uncertainty: 0.0,
+        description: "speed of light in vacuum",
+    },
+    Constant {
+        name: "h",
+        value: 6.62607015e-34,
+        unit: "J s",
+        uncertainty: 0.0,
+        description: "Planck constant",
+    },
+    Constant {
+        name: "hbar",
+        value: 1.054571817e-34,
+        unit: "J s",
+        uncertainty: 0.0,
+        description: "reduced Planck constant, h / (2 pi)",
+    },
+    Constant {
+        name: "G",
+        value: 6.67430e-11,
+        unit: "m^3 kg^-1 s^-2",
+        uncertainty: 0.00015e-11,
+        description: "Newtonian constant of gravitation",
+    },
+    Constant {
+        name: "k_B",
+        value: 1.380649e-23,
+        unit: "J/K",
+        uncertainty: 0.0,
+        description: "Boltzmann constant",
+    },
+    Constant {
+        name: "N_A",
+        value: 6.02214076e23,
+        unit: "mol^-1",
+        uncertainty: 0.0,
+        description: "Avogadro constant",
+    },
+    Constant {
+        name: "R",
+        value: 8.314462618,
+        unit: "J mol^-1 K^-1",
+        uncertainty: 0.0,
+        description: "molar gas constant, N_A k_B",
+    },
+    Constant {
+        name: "q_e",
+        value: 1.602176634e-19,
+        unit: "C",
+        uncertainty: 0.0,
+        description: "elementary charge",
+    },
+    Constant {
+        name: "m_e",
+        value: 9.1093837015e-31,
+        unit: "kg",
+        uncertainty: 0.0000000028e-31,
+        description: "electron mass",
+    },
+    Constant {
+        name: "m_p",
+        value: 1.67262192369e-27,
+        unit: "kg",
+        uncertainty: 0.00000000051e-27,
+        description: "proton mass",
+    },
+    Constant {
+        name: "epsilon_0",
+        value: 8.8541878128e-12,
+        unit: "F/m",
+        uncertainty: 0.0000000013e-12,
+        description: "vacuum electric permittivity",
+    },
+    Constant {
+        name: "g_n",
+        value: 9.80665,
+        unit: "m/s^2",
+        uncertainty: 0.0,
+        description: "standard acceleration of gravity",
+    },
+];
+
+pub fn lookup(name: &str) -> Option<&'static Constant> {
+    CONSTANTS.iter().find(|c| c.name == name)
+}
@@ -0,0 +1,315 @@
+//! Currencies as units, with exchange rates read from a local file. The
+//! file lists how much of every currency one unit of the base currency
+//! buys, either as CSV lines `USD,1.087` or as JSON
+//! `{"base": "EUR", "rates": {"USD": 1.087}}`. The base currency is the
+//! one with the rate 1 in a CSV file.
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    dates,
+    units::{Dimension, Unit},
+};
+
+/// Exchange rates loaded from a file
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rates {
+    pub path: PathBuf,
+    /// when the file was last changed
+    pub modified: SystemTime,
+    pub base: String,
+    /// a unit for every currency, measured in the base currency
+    pub currencies: Vec<Unit>,
+}
+
+impl Rates {
+    pub fn load(path: &Path) -> Result<Rates, String> {
+        let error = |e: std::io::Error| format!("Unable to read rates {}: {}", path.display(), e);
+        let modified = fs::metadata(path)
+            .and_then(|m| m.modified())
+            .map_err(error)?;
+        let text = fs::read_to_string(path).map_err(error)?;
+        let json = path.extension().is_some_and(|e| e == "json");
+        let (base, rates) = if json {
+            parse_json(&text)
+        } else {
+            parse_csv(&text)
+        }
+        .map_err(|e| format!("Invalid rates {}: {}", path.display(), e))?;
+        let currencies = rates
+            .into_iter()
+            .map(|(code, rate)| Unit {
+                name: code,
+                factor: 1.0 / rate,
+                dimension: Dimension::new(&[(&base, 1)]),
+                percent: false,
+            })
+            .collect();
+        Ok(Rates {
+            path: path.to_path_buf(),
+            modified,
+            base,
+            currencies,
+        })
+    }
+
+    pub fn lookup(&self, code: &str) -> Option<Unit> {
+        self.currencies.iter().find(|c| c.name == code).cloned()
+    }
+
+    /// The time the file was last changed, in UTC
+    pub fn timestamp(&self) -> String {
+        let seconds = self
+            .modified
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        format_utc(seconds)
+    }
+}
+
+/// Expands a leading `~/` to the home directory
+pub fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), env::var_os("HOME")) {
+        (Some(rest), Some(home)) => PathBuf::from(home).join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+/// Formats seconds since 1970 as `2026-10-19 14:05 UTC`
+fn format_utc(seconds: u64) -> String {
+    let (year, month, day) = dates::civil_from_days((seconds / 86400) as i64);
+    let minutes = seconds % 86400 / 60;
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02} UTC",
+        minutes / 60,
+        minutes % 60
+    )
+}
+
+fn currency(code: &str, rate: f64) -> Result<(String, f64), String> {
+    let valid = code.chars().next().is_some_and(|c| c.is_ascii_alphabetic())
+        && code.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !valid {
+        return Err(format!("{code} is not a valid currency code"));
+    }
+    if !(rate.is_finite() && rate > 0.0) {
+        return Err(format!("the rate of {code} must be a positive number"));
+    }
+    Ok((code.to_string(), rate))
+}
+
+/// Reads `code,rate` lines, skipping blank lines, comments starting with
+/// `#` and a header line
+fn parse_csv(text: &str) -> Result<(String, Vec<(String, f64)>), String> {
+    let mut rates = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (code, rate) = line
+            .split_once(',')
+            .ok_or(format!("line {}: expected currency,rate", i + 1))?;
+        let rate = match rate.trim().parse::<f64>() {
+            Ok(rate) => rate,
+            Err(_) if rates.is_empty() => continue,
+            Err(_) => return Err(format!("line {}: {} is not a rate", i + 1, rate.trim())),
+        };
+        rates.push(currency(code.trim(), rate).map_err(|e| format!("line {}: {}", i + 1, e))?);
+    }
+    let base = rates
+        .iter()
+        .find(|(_, rate)| *rate == 1.0)
+        .ok_or("the base currency with the rate 1 is missing")?
+        .0
+        .clone();
+    Ok((base, rates))
+}
+
+fn parse_json(text: &str) -> Result<(String, Vec<(String, f64)>), String> {
+    let mut parser = Json {
+        chars: text.chars().collect(),
+        i: 0,
+    };
+    let document = parser.value()?;
+    let field = |name: &str| match &document {
+        JsonValue::Object(fields) => fields.iter().find(|(k, _)| k == name).map(|(_, v)| v),
+        _ => None,
+    };
+    let Some(JsonValue::String(base)) = field("base") else {
+        return Err("expected a \"base\" currency".to_string());
+    };
+    let Some(JsonValue::Object(fields)) = field("rates") else {
+        return Err("expected an object of \"rates\"".to_string());
+    };
+    let mut rates = vec![currency(base, 1.0)?];
+    for (code, rate) in fields {
+        let JsonValue::Number(rate) = rate else {
+            return Err(format!("the rate of {code} must be a number"));
+        };
+        if code != base {
+            rates.push(currency(code, *rate)?);
+        }
+    }
+    Ok((base.clone(), rates))
+}
+
+enum JsonValue {
+    Object(Vec<(String, JsonValue)>),
+    String(String),
+    Number(f64),
+    /// arrays, booleans and null, which rates files do not need
+    Other,
+}
+
+/// Just enough of a JSON parser to read a rates file
+struct Json {
+    chars: Vec<char>,
+    i: usize,
+}
+
+impl Json {
+    fn skip_whitespace(&mut self) {
+        while self.chars.get(self.i).is_some_and(|c| c.is_whitespace()) {
+            self.i += 1;
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), String> {
+        self.skip_whitespace();
+        if self.chars.get(self.i) != Some(&c) {
+            return Err(format!("expected '{c}' at character {}", self.i));
+        }
+        self.i += 1;
+        Ok(())
+    }
+
+    fn value(&mut self) -> Result<JsonValue, String> {
+        self.skip_whitespace();
+        match self.chars.get(self.i) {
+            Some('{') => {
+                self.i += 1;
+                let mut fields = Vec::new();
+                self.skip_whitespace();
+                if self.chars.get(self.i) == Some(&'}') {
+                    self.i += 1;
+                    return Ok(JsonValue::Object(fields));
+                }
+                loop {
+                    self.skip_whitespace();
+                    let key = self.string()?;
+                    self.expect(':')?;
+                    fields.push((key, self.value()?));
+                    self.skip_whitespace();
+                    match self.chars.get(self.i) {
+                        Some(',') => self.i += 1,
+                        _ => break,
+                    }
+                }
+                self.expect('}')?;
+                Ok(JsonValue::Object(fields))
+            }
+            Some('[') => {
+                self.i += 1;
+                self.skip_whitespace();
+                if self.chars.get(self.i) == Some(&']') {
+                    self.i += 1;
+                    return Ok(JsonValue::Other);
+                }
+                loop {
+                    self.value()?;
+                    self.skip_whitespace();
+                    match self.chars.get(self.i) {
+                        Some(',') => self.i += 1,
+                        _ => break,
+                    }
+                }
+                self.expect(']')?;
+                Ok(JsonValue::Other)
+            }
+            Some('"') => Ok(JsonValue::String(self.string()?)),
+            Some(_) => {
+                let start = self.i;
+                while self
+                    .chars
+                    .get(self.i)
+                    .is_some_and(|c| c.is_ascii_alphanumeric() || "+-.".contains(*c))
+                {
+                    self.i += 1;
+                }
+                let word: String = self.chars[start..self.i].iter().collect();
+                match word.as_str() {
+                    "true" | "false" | "null" => Ok(JsonValue::Other),
+                    _ => word
+                        .parse()
+                        .map(JsonValue::Number)
+                        .map_err(|_| format!("unexpected '{word}' at character {start}")),
+                }
+            }
+            None => Err("unexpected end of file".to_string()),
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        if self.chars.get(self.i) != Some(&'"') {
+            return Err(format!("expected a string at character {}", self.i));
+        }
+        self.i += 1;
+        let mut string = String::new();
+        loop {
+            match self.chars.get(self.i) {
+                Some('"') => break,
+                Some('\\') => {
+                    self.i += 1;
+                    match self.chars.get(self.i) {
+                        Some('n') => string.push('\n'),
+                        Some('t') => string.push('\t'),
+                        Some(&c) => string.push(c),
+                        None => return Err("unexpected end of file".to_string()),
+                    }
+                }
+                Some(&c) => string.push(c),
+                None => return Err("unexpected end of file".to_string()),
+            }
+            self.i += 1;
+        }
+        self.i += 1;
+        Ok(string)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_rates() {
+        let (base, rates) = parse_csv("currency,rate\n# ECB\nEUR,1\nUSD, 1.087\n").unwrap();
+        assert_eq!(base, "EUR");
+        assert_eq!(rates[1], ("USD".to_string(), 1.087));
+        assert!(parse_csv("USD,1.087").is_err());
+        assert!(parse_csv("EUR,1\nUSD,-1").is_err());
+        assert!(parse_csv("EUR,1\nUSD,x").is_err());
+
+        let json =
+            r#"{"base": "EUR", "date": "2026-10-19", "rates": {"USD": 1.087, "JPY": 162.5}}"#;
+        let (base, rates) = parse_json(json).unwrap();
+        assert_eq!(base, "EUR");
+        assert_eq!(rates.len(), 3);
+        assert_eq!(rates[2], ("JPY".to_string(), 162.5));
+        assert!(parse_json(r#"{"rates": {}}"#).is_err());
+        assert!(parse_json(r#"{"base": "EUR", "rates": {"USD": "1"}}"#).is_err());
+        assert!(parse_json(r#"{"base": "EUR""#).is_err());
+    }
+
+    #[test]
+    fn formats_timestamps() {
+        assert_eq!(format_utc(0), "1970-01-01 00:00 UTC");
+        assert_eq!(format_utc(951782400 + 3660), "2000-02-29 01:01 UTC");
+        assert_eq!(format_utc(1792411200), "2026-10-19 12:00 UTC");
+    }
+}
@@ -0,0 +1,287 @@
+//! Dates and times. A date is an instant, the seconds since 1970-01-01
+//! 00:00 UTC, together with the offset of the time zone it is shown in.
+//! Durations are quantities of time.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::units::{self, Dimension, Quantity};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DateTime {
+    pub seconds: f64,
+    /// offset of the time zone from UTC in minutes
+    pub offset: i32,
+}
+
+impl DateTime {
+    /// A date and time given in the time zone `offset`
+    pub fn from_local(days: i64, seconds: f64, offset: i32) -> DateTime {
+        DateTime {
+            seconds: (days * 86400) as f64 + seconds - (offset * 60) as f64,
+            offset,
+        }
+    }
+
+    pub fn now(offset: i32) -> DateTime {
+        let seconds = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0.0, |d| d.as_secs_f64());
+        DateTime { seconds, offset }
+    }
+
+    /// Midnight of the current day in the time zone `offset`
+    pub fn today(offset: i32) -> DateTime {
+        let (days, _) = DateTime::now(offset).local();
+        DateTime::from_local(days, 0.0, offset)
+    }
+
+    /// The day since 1970 and the seconds since midnight in the time zone
+    /// of the date
+    pub fn local(&self) -> (i64, f64) {
+        let seconds = self.seconds + (self.offset * 60) as f64;
+        let days = seconds.div_euclid(86400.0);
+        (days as i64, seconds - days * 86400.0)
+    }
+
+    /// The year, month and day
+    pub fn date(&self) -> (i64, u32, u32) {
+        civil_from_days(self.local().0)
+    }
+
+    /// The day of the week, 1 for Monday to 7 for Sunday
+    pub fn weekday(&self) -> u32 {
+        (self.local().0 + 3).rem_euclid(7) as u32 + 1
+    }
+
+    pub fn in_zone(&self, offset: i32) -> DateTime {
+        DateTime {
+            seconds: self.seconds,
+            offset,
+        }
+    }
+
+    /// Moves the date by a duration. Whole months and years move it in the
+    /// calendar, to the same day of the month and the same time.
+    pub fn add(&self, duration: &Quantity) -> Result<DateTime, String> {
+        if !is_duration(duration) {
+            return Err(format!("Cannot add {} to a date", duration));
+        }
+        if let Some(months) = calendar_months(duration) {
+            return Ok(self.add_months(months));
+        }
+        Ok(DateTime {
+            seconds: self.seconds + duration.si_value(),
+            offset: self.offset,
+        })
+    }
+
+    /// The same day and time `months` later, or the last day of the month
+    /// if it is shorter
+    fn add_months(&self, months: i64) -> DateTime {
+        let (days, seconds) = self.local();
+        let (year, month, day) = civil_from_days(days);
+        let months = year * 12 + month as i64 - 1 + months;
+        let (year, month) = (months.div_euclid(12), months.rem_euclid(12) as u32 + 1);
+        let days = (1..=day)
+            .rev()
+            .find_map(|day| checked_days(year, month, day))
+            .unwrap();
+        DateTime::from_local(days, seconds, self.offset)
+    }
+
+    /// The time from `other` to this date, in days, hours, minutes or
+    /// seconds, whichever is the largest that is at most as long
+    pub fn since(&self, other: &DateTime) -> Quantity {
+        let seconds = self.seconds - other.seconds;
+        let unit = ["d", "h", "min", "s"]
+            .iter()
+            .filter_map(|name| units::lookup(name))
+            .find(|u| seconds.abs() >= u.factor)
+            .unwrap_or_else(|| units::lookup("s").unwrap());
+        Quantity {
+            value: seconds / unit.factor,
+            unit,
+        }
+    }
+
+    /// Shows the date, the time unless it is midnight and, if asked for,
+    /// the time zone
+    pub fn format(&self, zone: bool) -> String {
+        let (days, seconds) = self.local();
+        let (year, month, day) = civil_from_days(days);
+        let mut text = format!("{year:04}-{month:02}-{day:02}");
+        if seconds != 0.0 {
+            let minutes = (seconds / 60.0).floor();
+            text += &format!(" {:02}:{:02}", minutes as i64 / 60, minutes as i64 % 60);
+            let seconds = seconds - minutes * 60.0;
+            if seconds != 0.0 {
+                text += &format!(":{:02}", seconds.floor());
+                let fraction = seconds.fract();
+                if fraction != 0.0 {
+                    text += &format!("{:.3}", fraction)[1..];
+                }
+            }
+        }
+        if zone {
+            text += " ";
+            text += &format_zone(self.offset);
+        }
+        text
+    }
+
+    /// ISO 8601, e.g. `2026-10-18T14:30:00+02:00`, with all the digits
+    /// needed to read back the same instant
+    pub fn iso(&self) -> String {
+        let (days, seconds) = self.local();
+        let (year, month, day) = civil_from_days(days);
+        let minutes = (seconds / 60.0).floor();
+        let seconds = seconds - minutes * 60.0;
+        let sign = if self.offset < 0 { '-' } else { '+' };
+        format!(
+            "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{}{}{sign}{:02}:{:02}",
+            minutes as i64 / 60,
+            minutes as i64 % 60,
+            if seconds < 10.0 { "0" } else { "" },
+            seconds,
+            self.offset.abs() / 60,
+            self.offset.abs() % 60
+        )
+    }
+}
+
+impl std::fmt::Display for DateTime {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.format(self.offset != 0))
+    }
+}
+
+/// The units of durations that are counted in calendar months, with the
+/// number of months in one
+const CALENDAR_UNITS: [(&str, f64); 2] = [("month", 1.0), ("yr", 12.0)];
+
+/// The number of months of a whole number of months or years
+fn calendar_months(duration: &Quantity) -> Option<i64> {
+    let (_, months) = CALENDAR_UNITS
+        .iter()
+        .find(|(name, _)| duration.unit.name == *name)?;
+    let months = duration.value * months;
+    (months.fract() == 0.0 && months.abs() < 1e9).then_some(months as i64)
+}
+
+pub fn is_duration(quantity: &Quantity) -> bool {
+    quantity.unit.dimension == Dimension::new(&[("s", 1)])
+}
+
+/// Days since 1970-01-01 of a date in the proleptic Gregorian calendar,
+/// after Howard Hinnant's days_from_civil
+pub fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let month = month as i64;
+    let day_of_year =
+        (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+/// The year, month and day of a day since 1970-01-01
+pub fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+    (year, month, day)
+}
+
+/// The day of a valid date, None for e.g. February 30
+pub fn checked_days(year: i64, month: u32, day: u32) -> Option<i64> {
+    let days = days_from_civil(year, month, day);
+    ((1..=12).contains(&month) && civil_from_days(days) == (year, month, day)).then_some(days)
+}
+
+/// Parses a time zone offset like `UTC`, `UTC+2` or `UTC-05:30` into
+/// minutes
+pub fn parse_zone(zone: &str) -> Option<i32> {
+    let offset = zone.strip_prefix("UTC")?;
+    if offset.is_empty() {
+        return Some(0);
+    }
+    parse_offset(offset)
+}
+
+/// Parses `+2`, `-05:30` or `+0530` into minutes
+pub fn parse_offset(offset: &str) -> Option<i32> {
+    let sign = match offset.chars().next()? {
+        '+' => 1,
+        '-' => -1,
+        _ => return None,
+    };
+    let digits = &offset[1..];
+    let (hours, minutes) = match digits.split_once(':') {
+        Some((h, m)) if m.len() == 2 => (h, m),
+        None if digits.len() == 4 => digits.split_at(2),
+        None if digits.len() <= 2 => (digits, "0"),
+        _ => return None,
+    };
+    if hours.is_empty() || !(hours.len() <= 2 && hours.chars().all(|c| c.is_ascii_digit())) {
+        return None;
+    }
+    let (hours, minutes) = (hours.parse::<i32>().ok()?, minutes.parse::<i32>().ok()?);
+    (hours <= 14 && minutes < 60).then_some(sign * (hours * 60 + minutes))
+}
+
+pub fn format_zone(offset: i32) -> String {
+    if offset == 0 {
+        return "UTC".to_string();
+    }
+    let sign = if offset < 0 { '-' } else { '+' };
+    let (hours, minutes) = (offset.abs() / 60, offset.abs() % 60);
+    if minutes == 0 {
+        format!("UTC{sign}{hours}")
+    } else {
+        format!("UTC{sign}{hours}:{minutes:02}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_calendar_dates() {
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        assert_eq!(days_from_civil(2000, 3, 1), 11017);
+        assert_eq!(civil_from_days(11016), (2000, 2, 29));
+        assert_eq!(civil_from_days(-1), (1969, 12, 31));
+        assert_eq!(checked_days(2026, 2, 29), None);
+        assert_eq!(checked_days(2026, 13, 1), None);
+        let date = DateTime::from_local(days_from_civil(2026, 10, 18), 14.5 * 3600.0, 120);
+        assert_eq!(date.to_string(), "2026-10-18 14:30 UTC+2");
+        assert_eq!(date.in_zone(0).format(false), "2026-10-18 12:30");
+        assert_eq!(date.iso(), "2026-10-18T14:30:00+02:00");
+        assert_eq!(date.weekday(), 7);
+        assert_eq!(
+            DateTime::from_local(0, 0.0, -330).iso(),
+            "1970-01-01T00:00:00-05:30"
+        );
+    }
+
+    #[test]
+    fn parses_zones() {
+        assert_eq!(parse_zone("UTC"), Some(0));
+        assert_eq!(parse_zone("UTC+2"), Some(120));
+        assert_eq!(parse_zone("UTC-05:30"), Some(-330));
+        assert_eq!(parse_offset("+0545"), Some(345));
+        assert_eq!(parse_zone("UTC+15"), None);
+        assert_eq!(parse_zone("GMT"), None);
+        assert_eq!(format_zone(-330), "UTC-5:30");
+        assert_eq!(format_zone(0), "UTC");
+    }
+}
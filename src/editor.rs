@@ -0,0 +1,721 @@
+use std::{
+    env,
+    fs::{self, OpenOptions},
+    io::{self, BufRead, IsTerminal, Read, Write},
+    path::PathBuf,
+    process::{Command, Stdio},
+};
+
+/// Outcome of reading one line from the user
+#[derive(Debug, PartialEq)]
+pub enum Line {
+    Input(String),
+    /// Ctrl-C was pressed, the line being typed was thrown away
+    Interrupted,
+    /// Ctrl-D on an empty line or the input was closed
+    Eof,
+}
+
+/// A possible completion of the word before the cursor
+#[derive(Debug, PartialEq, Clone)]
+pub struct Candidate {
+    /// text replacing the word
+    pub replacement: String,
+    /// how the candidate is listed when there are several
+    pub display: String,
+    /// shown below the line when this is the only candidate
+    pub hint: Option<String>,
+}
+
+/// Hooks the REPL provides to the editor while a line is being typed
+pub trait Helper {
+    /// Returns the byte offset in `line` (the text before the cursor) where
+    /// the word being completed starts, and the candidates for it
+    fn complete(&self, line: &str) -> (usize, Vec<Candidate>) {
+        (line.len(), Vec::new())
+    }
+
+    /// Returns `line` decorated with ANSI escape codes, which must not change
+    /// its visible width. `cursor` is a character index into `line`.
+    fn highlight(&self, line: &str, _cursor: usize) -> String {
+        line.to_string()
+    }
+}
+
+impl Helper for () {}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Key {
+    Char(char),
+    Ctrl(char),
+    Enter,
+    Tab,
+    Backspace,
+    Delete,
+    Left,
+    Right,
+    Up,
+    Down,
+    Home,
+    End,
+    Escape,
+    Unknown,
+}
+
+/// Previously entered lines, oldest first, optionally persisted to a file
+pub struct History {
+    entries: Vec<String>,
+    path: Option<PathBuf>,
+}
+
+impl History {
+    const MAX_ENTRIES: usize = 1000;
+
+    pub fn new() -> History {
+        History {
+            entries: Vec::new(),
+            path: None,
+        }
+    }
+
+    /// Loads the history stored in `path`; new entries get appended to it.
+    /// A missing file just starts an empty history. A file with more than
+    /// `MAX_ENTRIES` lines is rewritten with the newest ones.
+    pub fn load(path: PathBuf) -> History {
+        let lines = match fs::read_to_string(&path) {
+            Ok(content) => content.lines().map(|l| l.to_string()).collect(),
+            Err(_) => Vec::new(),
+        };
+        let total = lines.len();
+        let mut history = History {
+            entries: lines.into_iter().filter(|l| !l.trim().is_empty()).collect(),
+            path: Some(path),
+        };
+        history.truncate();
+        if total > history.entries.len() {
+            if let Some(path) = &history.path {
+                let _ = Self::rewrite(path, &history.entries);
+            }
+        }
+        history
+    }
+
+    /// `$XDG_DATA_HOME/calculator-rs/history`, falling back to
+    /// `~/.local/share/calculator-rs/history`
+    pub fn default_path() -> Option<PathBuf> {
+        let data_home = match env::var_os("XDG_DATA_HOME") {
+            Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+            _ => PathBuf::from(env::var_os("HOME")?).join(".local/share"),
+        };
+        Some(data_home.join("calculator-rs").join("history"))
+    }
+
+    pub fn entries(&self) -> &[String] {
+        &self.entries
+    }
+
+    /// Adds a line to the history, skipping blank lines and immediate repeats
+    pub fn add(&mut self, line: &str) {
+        let line = line.trim();
+        if line.is_empty() || self.entries.last().is_some_and(|last| last == line) {
+            return;
+        }
+        self.entries.push(line.to_string());
+        self.truncate();
+        if let Some(path) = &self.path {
+            // history is a convenience, failing to persist it is not an error
+            let _ = Self::append(path, line);
+        }
+    }
+
+    /// Finds the newest entry before `before` containing `query`
+    pub fn search(&self, query: &str, before: usize) -> Option<usize> {
+        let end = before.min(self.entries.len());
+        self.entries[..end].iter().rposition(|e| e.contains(query))
+    }
+
+    fn append(path: &PathBuf, line: &str) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        writeln!(file, "{line}")
+    }
+
+    fn rewrite(path: &PathBuf, entries: &[String]) -> io::Result<()> {
+        let mut content = entries.join("\n");
+        content.push('\n');
+        fs::write(path, content)
+    }
+
+    fn truncate(&mut self) {
+        if self.entries.len() > Self::MAX_ENTRIES {
+            let excess = self.entries.len() - Self::MAX_ENTRIES;
+            self.entries.drain(..excess);
+        }
+    }
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The line being edited together with the cursor position, in characters
+#[derive(Debug, Default, PartialEq)]
+struct Buffer {
+    chars: Vec<char>,
+    cursor: usize,
+}
+
+impl Buffer {
+    fn set(&mut self, line: &str) {
+        self.chars = line.chars().collect();
+        self.cursor = self.chars.len();
+    }
+
+    fn text(&self) -> String {
+        self.chars.iter().collect()
+    }
+
+    fn insert(&mut self, c: char) {
+        self.chars.insert(self.cursor, c);
+        self.cursor += 1;
+    }
+
+    fn backspace(&mut self) {
+        if self.cursor > 0 {
+            self.cursor -= 1;
+            self.chars.remove(self.cursor);
+        }
+    }
+
+    fn delete(&mut self) {
+        if self.cursor < self.chars.len() {
+            self.chars.remove(self.cursor);
+        }
+    }
+
+    fn left(&mut self) {
+        self.cursor = self.cursor.saturating_sub(1);
+    }
+
+    fn right(&mut self) {
+        self.cursor = (self.cursor + 1).min(self.chars.len());
+    }
+
+    /// Replaces the characters between `start` and the cursor
+    fn replace(&mut self, start: usize, text: &str) {
+        let len = text.chars().count();
+        self.chars.splice(start..self.cursor, text.chars());
+        self.cursor = start + len;
+    }
+
+    fn kill_to_end(&mut self) {
+        self.chars.truncate(self.cursor);
+    }
+
+    fn kill_to_start(&mut self) {
+        self.chars.drain(..self.cursor);
+        self.cursor = 0;
+    }
+
+    /// Deletes the word before the cursor, like Ctrl-W in a shell
+    fn kill_word(&mut self) {
+        let mut start = self.cursor;
+        while start > 0 && self.chars[start - 1] == ' ' {
+            start -= 1;
+        }
+        while start > 0 && self.chars[start - 1] != ' ' {
+            start -= 1;
+        }
+        self.chars.drain(start..self.cursor);
+        self.cursor = start;
+    }
+}
+
+/// Puts the terminal into raw mode for as long as it is alive, using `stty`
+/// so that no platform specific termios bindings are needed. Output
+/// processing stays on, so results printed between lines look as usual.
+struct RawMode {
+    saved: String,
+}
+
+impl RawMode {
+    fn enable() -> io::Result<RawMode> {
+        let output = Command::new("stty")
+            .arg("-g")
+            .stdin(Stdio::inherit())
+            .output()?;
+        if !output.status.success() {
+            return Err(io::Error::other("stty failed to read terminal settings"));
+        }
+        let saved = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        Self::stty(&["raw", "-echo", "opost"])?;
+        Ok(RawMode { saved })
+    }
+
+    fn stty(args: &[&str]) -> io::Result<()> {
+        let status = Command::new("stty")
+            .args(args)
+            .stdin(Stdio::inherit())
+            .status()?;
+        if status.success() {
+            Ok(())
+        } else {
+            Err(io::Error::other("stty failed to change terminal settings"))
+        }
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        let _ = Self::stty(&[&self.saved]);
+    }
+}
+
+/// A small line editor with cursor movement, history navigation and
+/// reverse search. Falls back to plain line reading when stdin is not a
+/// terminal, e.g. when formulas are piped in. The terminal stays in raw mode
+/// from the first line read until the editor is dropped.
+pub struct Editor {
+    history: History,
+    interactive: bool,
+    raw: Option<RawMode>,
+}
+
+impl Editor {
+    pub fn new(history: History) -> Editor {
+        Editor {
+            history,
+            interactive: io::stdin().is_terminal() && io::stdout().is_terminal(),
+            raw: None,
+        }
+    }
+
+    pub fn history(&self) -> &History {
+        &self.history
+    }
+
+    pub fn add_history(&mut self, line: &str) {
+        self.history.add(line);
+    }
+
+    pub fn read_line(&mut self, prompt: &str, helper: &dyn Helper) -> io::Result<Line> {
+        if self.interactive && self.raw.is_none() {
+            self.raw = RawMode::enable().ok();
+            self.interactive = self.raw.is_some();
+        }
+        if self.interactive {
+            return self.edit(prompt, helper);
+        }
+        Self::read_plain(&mut io::stdin().lock())
+    }
+
+    fn read_plain(input: &mut impl BufRead) -> io::Result<Line> {
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(Line::Eof);
+        }
+        Ok(Line::Input(line.trim_end_matches(['\n', '\r']).to_string()))
+    }
+
+    fn edit(&mut self, prompt: &str, helper: &dyn Helper) -> io::Result<Line> {
+        let mut stdin = io::stdin().lock();
+        let mut stdout = io::stdout().lock();
+        let mut buffer = Buffer::default();
+        // index into the history while browsing it, len() means the new line
+        let mut position = self.history.entries.len();
+        let mut draft = String::new();
+        Self::render(&mut stdout, prompt, &buffer, helper)?;
+        loop {
+            let key = match Self::read_key(&mut stdin)? {
+                Some(key) => key,
+                None => return Ok(Line::Eof),
+            };
+            match key {
+                Key::Enter => {
+                    write!(stdout, "\r\n")?;
+                    stdout.flush()?;
+                    return Ok(Line::Input(buffer.text()));
+                }
+                Key::Ctrl('c') => {
+                    write!(stdout, "^C\r\n")?;
+                    stdout.flush()?;
+                    return Ok(Line::Interrupted);
+                }
+                Key::Ctrl('d') => {
+                    if buffer.chars.is_empty() {
+                        write!(stdout, "\r\n")?;
+                        stdout.flush()?;
+                        return Ok(Line::Eof);
+                    }
+                    buffer.delete();
+                }
+                Key::Char(c) => buffer.insert(c),
+                Key::Tab => Self::complete(&mut stdout, &mut buffer, helper)?,
+                Key::Backspace | Key::Ctrl('h') => buffer.backspace(),
+                Key::Delete => buffer.delete(),
+                Key::Left | Key::Ctrl('b') => buffer.left(),
+                Key::Right | Key::Ctrl('f') => buffer.right(),
+                Key::Home | Key::Ctrl('a') => buffer.cursor = 0,
+                Key::End | Key::Ctrl('e') => buffer.cursor = buffer.chars.len(),
+                Key::Ctrl('k') => buffer.kill_to_end(),
+                Key::Ctrl('u') => buffer.kill_to_start(),
+                Key::Ctrl('w') => buffer.kill_word(),
+                Key::Ctrl('l') => write!(stdout, "\x1b[2J\x1b[H")?,
+                Key::Up | Key::Ctrl('p') if position > 0 => {
+                    if position == self.history.entries.len() {
+                        draft = buffer.text();
+                    }
+                    position -= 1;
+                    buffer.set(&self.history.entries[position]);
+                }
+                Key::Down | Key::Ctrl('n') if position < self.history.entries.len() => {
+                    position += 1;
+                    match self.history.entries.get(position) {
+                        Some(entry) => buffer.set(entry),
+                        None => buffer.set(&draft),
+                    }
+                }
+                Key::Ctrl('r') => {
+                    if let Some(line) = self.reverse_search(&mut stdin, &mut stdout, &mut buffer)? {
+                        return Ok(line);
+                    }
+                }
+                _ => {}
+            }
+            Self::render(&mut stdout, prompt, &buffer, helper)?;
+        }
+    }
+
+    /// Incrementally searches the history backwards. Returns a line when the
+    /// search ends the whole edit (Enter or Ctrl-C), otherwise leaves the
+    /// match in `buffer` for further editing.
+    fn reverse_search(
+        &self,
+        stdin: &mut impl Read,
+        stdout: &mut impl Write,
+        buffer: &mut Buffer,
+    ) -> io::Result<Option<Line>> {
+        let original = buffer.text();
+        let mut query = String::new();
+        let mut found: Option<usize> = None;
+        loop {
+            let shown = found.map_or("", |i| self.history.entries[i].as_str());
+            write!(stdout, "\r(reverse-i-search)'{query}': {shown}\x1b[K")?;
+            stdout.flush()?;
+            let key = match Self::read_key(stdin)? {
+                Some(key) => key,
+                None => return Ok(Some(Line::Eof)),
+            };
+            match key {
+                Key::Char(c) => {
+                    query.push(c);
+                    found = self.history.search(&query, self.history.entries.len());
+                }
+                Key::Backspace | Key::Ctrl('h') => {
+                    query.pop();
+                    found = self.history.search(&query, self.history.entries.len());
+                }
+                Key::Ctrl('r') => {
+                    let before = found.unwrap_or(self.history.entries.len());
+                    if let Some(i) = self.history.search(&query, before) {
+                        found = Some(i);
+                    }
+                }
+                Key::Ctrl('c') | Key::Ctrl('g') => {
+                    if key == Key::Ctrl('c') {
+                        write!(stdout, "\r\x1b[K^C\r\n")?;
+                        stdout.flush()?;
+                        return Ok(Some(Line::Interrupted));
+                    }
+                    buffer.set(&original);
+                    return Ok(None);
+                }
+                Key::Enter => {
+                    let line = found.map_or(original, |i| self.history.entries[i].clone());
+                    write!(stdout, "\r\x1b[K{line}\r\n")?;
+                    stdout.flush()?;
+                    return Ok(Some(Line::Input(line)));
+                }
+                _ => {
+                    if let Some(i) = found {
+                        buffer.set(&self.history.entries[i]);
+                    }
+                    return Ok(None);
+                }
+            }
+        }
+    }
+
+    /// Completes the word before the cursor. A unique candidate is inserted,
+    /// otherwise the common prefix of all candidates is, and if that does not
+    /// get any further the candidates are listed below the line.
+    fn complete(
+        stdout: &mut impl Write,
+        buffer: &mut Buffer,
+        helper: &dyn Helper,
+    ) -> io::Result<()> {
+        let before: String = buffer.chars[..buffer.cursor].iter().collect();
+        let (offset, candidates) = helper.complete(&before);
+        let start = before[..offset].chars().count();
+        match candidates.as_slice() {
+            [] => write!(stdout, "\x07")?,
+            [only] => {
+                buffer.replace(start, &only.replacement);
+                if let Some(hint) = &only.hint {
+                    write!(stdout, "\r\n{hint}\x1b[K\r\n")?;
+                }
+            }
+            _ => {
+                let common =
+                    candidates[1..]
+                        .iter()
+                        .fold(candidates[0].replacement.clone(), |common, c| {
+                            common
+                                .chars()
+                                .zip(c.replacement.chars())
+                                .take_while(|(a, b)| a == b)
+                                .map(|(a, _)| a)
+                                .collect()
+                        });
+                if common.chars().count() > buffer.cursor - start {
+                    buffer.replace(start, &common);
+                } else {
+                    let listing: Vec<&str> =
+                        candidates.iter().map(|c| c.display.as_str()).collect();
+                    write!(stdout, "\r\n{}\x1b[K\r\n", listing.join("  "))?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn render(
+        stdout: &mut impl Write,
+        prompt: &str,
+        buffer: &Buffer,
+        helper: &dyn Helper,
+    ) -> io::Result<()> {
+        let line = helper.highlight(&buffer.text(), buffer.cursor);
+        write!(stdout, "\r{prompt}{line}\x1b[K")?;
+        let column = prompt.chars().count() + buffer.cursor;
+        write!(stdout, "\r")?;
+        if column > 0 {
+            write!(stdout, "\x1b[{column}C")?;
+        }
+        stdout.flush()
+    }
+
+    fn read_byte(stdin: &mut impl Read) -> io::Result<Option<u8>> {
+        let mut byte = [0u8];
+        match stdin.read(&mut byte)? {
+            0 => Ok(None),
+            _ => Ok(Some(byte[0])),
+        }
+    }
+
+    /// Decodes one key press, including utf-8 characters and the ANSI escape
+    /// sequences sent for arrow, home, end and delete keys
+    fn read_key(stdin: &mut impl Read) -> io::Result<Option<Key>> {
+        let byte = match Self::read_byte(stdin)? {
+            Some(b) => b,
+            None => return Ok(None),
+        };
+        let key = match byte {
+            b'\r' | b'\n' => Key::Enter,
+            b'\t' => Key::Tab,
+            127 => Key::Backspace,
+            1..=26 => Key::Ctrl((b'a' + byte - 1) as char),
+            27 => match Self::read_byte(stdin)? {
+                Some(b'[') | Some(b'O') => match Self::read_byte(stdin)? {
+                    Some(b'A') => Key::Up,
+                    Some(b'B') => Key::Down,
+                    Some(b'C') => Key::Right,
+                    Some(b'D') => Key::Left,
+                    Some(b'H') => Key::Home,
+                    Some(b'F') => Key::End,
+                    Some(digit @ b'0'..=b'9') => {
+                        // sequences like ESC [ 3 ~, skip any modifiers
+                        let mut last = digit;
+                        while let Some(b) = Self::read_byte(stdin)? {
+                            if !(b.is_ascii_digit() || b == b';') {
+                                break;
+                            }
+                            last = b;
+                        }
+                        match last {
+                            b'1' | b'7' => Key::Home,
+                            b'3' => Key::Delete,
+                            b'4' | b'8' => Key::End,
+                            _ => Key::Unknown,
+                        }
+                    }
+                    _ => Key::Unknown,
+                },
+                _ => Key::Escape,
+            },
+            0..=31 => Key::Unknown,
+            _ => {
+                let width = match byte {
+                    0xc0..=0xdf => 2,
+                    0xe0..=0xef => 3,
+                    0xf0..=0xf7 => 4,
+                    _ => 1,
+                };
+                let mut bytes = vec![byte];
+                for _ in 1..width {
+                    match Self::read_byte(stdin)? {
+                        Some(b) => bytes.push(b),
+                        None => break,
+                    }
+                }
+                match String::from_utf8(bytes).ok().and_then(|s| s.chars().next()) {
+                    Some(c) => Key::Char(c),
+                    None => Key::Unknown,
+                }
+            }
+        };
+        Ok(Some(key))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Buffer, Candidate, Editor, Helper, History, Key, Line};
+
+    #[test]
+    fn history_skips_blank_and_repeated_lines() {
+        let mut history = History::new();
+        for line in ["1 + 1", "1 + 1", "  ", "let a = 2", "1 + 1"] {
+            history.add(line);
+        }
+        assert_eq!(history.entries(), ["1 + 1", "let a = 2", "1 + 1"]);
+    }
+
+    #[test]
+    fn history_file_keeps_the_newest_entries() {
+        let path =
+            std::env::temp_dir().join(format!("calculator-rs-history-{}", std::process::id()));
+        let lines: Vec<String> = (0..History::MAX_ENTRIES + 5)
+            .map(|i| i.to_string())
+            .collect();
+        std::fs::write(&path, lines.join("\n")).unwrap();
+        let mut history = History::load(path.clone());
+        assert_eq!(history.entries().len(), History::MAX_ENTRIES);
+        assert_eq!(history.entries()[0], "5");
+        history.add("x");
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(content.lines().count(), History::MAX_ENTRIES + 1);
+        assert_eq!(content.lines().next(), Some("5"));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn history_search_goes_backwards() {
+        let mut history = History::new();
+        for line in ["max(1,2)", "let a = 2", "max(a,3)", "a * 2"] {
+            history.add(line);
+        }
+        assert_eq!(history.search("max", 4), Some(2));
+        assert_eq!(history.search("max", 2), Some(0));
+        assert_eq!(history.search("max", 0), None);
+        assert_eq!(history.search("sin", 4), None);
+    }
+
+    #[test]
+    fn plain_input_ends_with_eof() {
+        let mut input: &[u8] = b"1 + 1\r\nexit";
+        assert_eq!(
+            Editor::read_plain(&mut input).unwrap(),
+            Line::Input("1 + 1".to_string())
+        );
+        assert_eq!(
+            Editor::read_plain(&mut input).unwrap(),
+            Line::Input("exit".to_string())
+        );
+        assert_eq!(Editor::read_plain(&mut input).unwrap(), Line::Eof);
+    }
+
+    #[test]
+    fn buffer_editing() {
+        let mut buffer = Buffer::default();
+        buffer.set("max(1,2)");
+        buffer.left();
+        buffer.insert(',');
+        buffer.insert('3');
+        assert_eq!(buffer.text(), "max(1,2,3)");
+        buffer.kill_word();
+        assert_eq!(buffer.text(), ")");
+        buffer.set("let a = 10");
+        buffer.kill_word();
+        buffer.backspace();
+        assert_eq!(buffer.text(), "let a =");
+    }
+
+    struct Words(&'static [&'static str]);
+
+    impl Helper for Words {
+        fn complete(&self, line: &str) -> (usize, Vec<Candidate>) {
+            let start = line.rfind(' ').map_or(0, |i| i + 1);
+            let candidates = self
+                .0
+                .iter()
+                .filter(|w| w.starts_with(&line[start..]))
+                .map(|w| Candidate {
+                    replacement: w.to_string(),
+                    display: w.to_string(),
+                    hint: Some(format!("{w}(x)")),
+                })
+                .collect();
+            (start, candidates)
+        }
+    }
+
+    #[test]
+    fn completion() {
+        let helper = Words(&["floor", "format", "sin"]);
+        let mut out = Vec::new();
+        let mut buffer = Buffer::default();
+
+        buffer.set("1 + f");
+        Editor::complete(&mut out, &mut buffer, &helper).unwrap();
+        assert_eq!(buffer.text(), "1 + f");
+        assert_eq!(String::from_utf8_lossy(&out), "\r\nfloor  format\x1b[K\r\n");
+
+        out.clear();
+        buffer.set("1 + fl");
+        Editor::complete(&mut out, &mut buffer, &helper).unwrap();
+        assert_eq!(buffer.text(), "1 + floor");
+        assert_eq!(String::from_utf8_lossy(&out), "\r\nfloor(x)\x1b[K\r\n");
+
+        buffer.set("s * 2");
+        buffer.cursor = 1;
+        Editor::complete(&mut out, &mut buffer, &helper).unwrap();
+        assert_eq!(buffer.text(), "sin * 2");
+        assert_eq!(buffer.cursor, 3);
+    }
+
+    #[test]
+    fn decodes_keys() {
+        let mut input: &[u8] = b"a\x1b[A\x1b[3~\x12\x7f\xc3\xa9\r";
+        let mut keys = Vec::new();
+        while let Some(key) = Editor::read_key(&mut input).unwrap() {
+            keys.push(key);
+        }
+        assert_eq!(
+            keys,
+            [
+                Key::Char('a'),
+                Key::Up,
+                Key::Delete,
+                Key::Ctrl('r'),
+                Key::Backspace,
+                Key::Char('é'),
+                Key::Enter,
+            ]
+        );
+    }
+}
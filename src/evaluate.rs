@@ -1,73 +1,1249 @@
-use std::collections::HashMap;
+use crate::{
+    ast::Expression,
+    dates::{self, DateTime},
+    distributions, finance,
+    interval::{self, Interval},
+    matrix, number_theory,
+    session::Session,
+    settings::AngleUnit,
+    statistics,
+    token::Token,
+    uncertainty::Uncertain,
+    units::{Quantity, Unit},
+    value::Value,
+    vector,
+};
 
-use crate::{ast::Expression, token::Token};
-
-pub fn evaluate(expr: Box<Expression>, variables: &mut HashMap<String, f32>) -> f32 {
-    match *expr {
-        Expression::Grouping(e) => evaluate(e, variables),
-        Expression::Literal(n) => n,
+pub fn evaluate(expr: &Expression, session: &mut Session) -> Result<Value, String> {
+    let value = match expr {
+        Expression::Grouping(e) => return evaluate(e, session),
+        Expression::Literal(n) if session.settings().intervals => {
+            return Ok(Value::Interval(Interval::around(*n)));
+        }
+        Expression::Literal(n) => *n,
         Expression::Unary(op, v) => {
-            let value = evaluate(v, variables);
-            if op == Token::Minus {
-                return -value;
+            let value = evaluate(v, session)?;
+            if let Value::Date(date) = value {
+                return Err(format!("Cannot negate the date {date}"));
+            }
+            if *op == Token::Minus {
+                return Ok(value.map(|n| -n));
             }
             unreachable!()
         }
         Expression::Binary(op, a, b) => {
-            let a = evaluate(a, variables);
-            let b = evaluate(b, variables);
-            match op {
-                Token::Minus => a - b,
-                Token::Plus => a + b,
-                Token::Star => a * b,
-                Token::Slash => a / b,
-                Token::Percent => a % b,
+            let a = evaluate(a, session)?;
+            let b = evaluate(b, session)?;
+            if *op == Token::At {
+                return matrix::product(&a, &b);
+            }
+            let op = match op {
+                Token::Minus => Value::sub,
+                Token::Plus => Value::add,
+                Token::Star => Value::mul,
+                Token::Slash => Value::div,
+                Token::Mod => Value::rem,
+                Token::Caret => Value::pow,
                 _ => unreachable!(),
+            };
+            return a.broadcast(&b, op);
+        }
+        Expression::List(items) => {
+            let items = items
+                .iter()
+                .map(|item| evaluate(item, session))
+                .collect::<Result<Vec<Value>, String>>()?;
+            return Ok(Value::List(items));
+        }
+        Expression::Index(list, i) => {
+            let list = evaluate(list, session)?;
+            let i = number(i, session)?;
+            return vector::index(list.as_list()?, i);
+        }
+        Expression::Slice(list, start, end) => {
+            let list = evaluate(list, session)?;
+            let start = start.as_ref().map(|s| number(s, session)).transpose()?;
+            let end = end.as_ref().map(|e| number(e, session)).transpose()?;
+            return vector::slice(list.as_list()?, start, end);
+        }
+        Expression::Postfix(Token::Percent, e) => {
+            return Ok(evaluate(e, session)?.map(|n| n / 100.0));
+        }
+        Expression::Percentage(op, a, p) => {
+            let a = evaluate(a, session)?;
+            let p = evaluate(p, session)?.map(|n| n / 100.0);
+            let part = a.broadcast(&p, Value::mul)?;
+            return match op {
+                Token::Plus => a.broadcast(&part, Value::add),
+                Token::Minus | Token::Off => a.broadcast(&part, Value::sub),
+                _ => Ok(part),
+            };
+        }
+        Expression::AsPercent(e) => {
+            // a percentage is shown as written rather than divided by 100
+            // and multiplied back
+            return match e.as_ref() {
+                Expression::Postfix(Token::Percent, p) => as_percent(&evaluate(p, session)?, 1.0),
+                e => as_percent(&evaluate(e, session)?, 100.0),
+            };
+        }
+        Expression::Postfix(Token::Bang, e) => {
+            let value = evaluate(e, session)?;
+            return lift("factorial", &[value], |x| {
+                number_call("factorial", x, session)
+            });
+        }
+        Expression::Postfix(op, e) => {
+            let value = number(e, session)?;
+            let unit = match op {
+                Token::Deg => AngleUnit::Degrees,
+                Token::Rad => AngleUnit::Radians,
+                Token::Grad => AngleUnit::Gradians,
+                _ => unreachable!(),
+            };
+            session.settings().angle.convert(value, unit)
+        }
+        Expression::Unit(e, unit) => {
+            let value = evaluate(e, session)?;
+            return with_unit(&value, &session.units().resolve(unit)?);
+        }
+        Expression::Convert(e, unit) => {
+            let value = evaluate(e, session)?;
+            return convert(&value, &session.units().resolve(unit)?);
+        }
+        Expression::DateTime(days, seconds, zone) => {
+            let offset = zone.unwrap_or(session.settings().timezone);
+            let days = days.unwrap_or_else(|| DateTime::today(offset).local().0);
+            return Ok(Value::Date(DateTime::from_local(days, *seconds, offset)));
+        }
+        Expression::InZone(e, offset) => {
+            return match evaluate(e, session)? {
+                Value::Date(date) => Ok(Value::Date(date.in_zone(*offset))),
+                value => Err(format!("Only dates have a time zone, not {value}")),
+            };
+        }
+        Expression::Uncertain(value, sigma) => {
+            // an uncertainty is far larger than rounding errors, the value
+            // and uncertainty are numbers even with intervals on
+            let intervals = std::mem::replace(&mut session.settings_mut().intervals, false);
+            let mut number = |e| match evaluate(e, session)? {
+                Value::Quantity(q) => Err(format!(
+                    "Uncertain values cannot have units yet, not {}",
+                    q.unit.name
+                )),
+                v => v.as_number(),
+            };
+            let operands = number(value).and_then(|value| Ok((value, number(sigma)?)));
+            session.settings_mut().intervals = intervals;
+            let (value, sigma) = operands?;
+            if sigma < 0.0 || sigma.is_nan() {
+                return Err(format!("An uncertainty must not be negative, not {sigma}"));
+            }
+            let source = session.new_source();
+            return Ok(Value::Uncertain(Uncertain::new(value, sigma, source)));
+        }
+        Expression::Interval(lo, hi) => {
+            let lo = evaluate(lo, session)?.as_interval()?;
+            let hi = evaluate(hi, session)?.as_interval()?;
+            return Ok(Value::Interval(Interval::new(lo.lo, hi.hi)?));
+        }
+        Expression::Compound(a, b) => {
+            let a = evaluate(a, session)?;
+            let b = evaluate(b, session)?;
+            // the result is in the last, usually the smallest, unit
+            return b.broadcast(&a, Value::add);
+        }
+        Expression::UnitDefinition(name, value) => {
+            let value = match value {
+                Some(value) => match evaluate(value, session)? {
+                    Value::Quantity(q) => Some(q),
+                    value => {
+                        return Err(format!(
+                            "A unit must be defined as a quantity with a unit, not {value}"
+                        ))
+                    }
+                },
+                None => None,
+            };
+            if session.variables().contains_key(name) {
+                return Err(format!("{name} is already a variable"));
+            }
+            let unit = session.units_mut().define(name, value.as_ref())?;
+            return Ok(Value::Quantity(
+                value.unwrap_or(Quantity { value: 1.0, unit }),
+            ));
+        }
+        Expression::SingleArity(Token::Sqrt, a) => {
+            let a = evaluate(a, session)?;
+            if let Value::Quantity(_) = a {
+                return Value::pow(&a, &Value::Number(0.5));
             }
+            return lift("sqrt", &[a], |x| {
+                domain("sqrt", x[0], x[0] >= 0.0, "x >= 0")?;
+                Ok(x[0].sqrt())
+            });
         }
         Expression::SingleArity(op, a) => {
-            let a = evaluate(a, variables);
-            match op {
-                Token::Sqrt => a.sqrt(),
-                Token::Floor => a.floor(),
-                Token::Ceil => a.ceil(),
-                Token::Cos => a.cos(),
-                Token::Sin => a.sin(),
-                Token::Tan => a.tan(),
-                _ => unreachable!(),
+            let mut a = evaluate(a, session)?;
+            let mut angle = session.settings().angle;
+            if matches!(op, Token::Cos | Token::Sin | Token::Tan) {
+                if let Value::Interval(i) = a {
+                    // intervals are bounded in radians
+                    let radians = Interval::around(angle.to_radians(1.0));
+                    a = Value::Interval(i.mul(&radians));
+                    angle = AngleUnit::Radians;
+                }
             }
+            return lift(&op.to_string(), &[a], |x| {
+                Ok(match op {
+                    Token::Floor => x[0].floor(),
+                    Token::Ceil => x[0].ceil(),
+                    Token::Cos => angle.to_radians(x[0]).cos(),
+                    Token::Sin => angle.to_radians(x[0]).sin(),
+                    Token::Tan => angle.to_radians(x[0]).tan(),
+                    _ => unreachable!(),
+                })
+            });
         }
         Expression::DoubleArity(op, a, b) => {
-            let a = evaluate(a, variables);
-            let b = evaluate(b, variables);
-            match op {
-                Token::Pow => a.powf(b),
-                Token::Log => a.log(b),
-                _ => unreachable!(),
-            }
+            let args = [evaluate(a, session)?, evaluate(b, session)?];
+            return lift(&op.to_string(), &args, |x| {
+                let (a, b) = (x[0], x[1]);
+                match op {
+                    Token::Pow => {
+                        let value = a.powf(b);
+                        if value.is_nan() && !a.is_nan() && !b.is_nan() {
+                            return Err(format!("pow({a}, {b}) is not a real number"));
+                        }
+                        Ok(value)
+                    }
+                    Token::Log => {
+                        domain("log", a, a > 0.0, "x > 0")?;
+                        if b <= 0.0 || b == 1.0 {
+                            return Err(format!("log base must be positive and not 1, not {b}"));
+                        }
+                        Ok(a.log(b))
+                    }
+                    _ => unreachable!(),
+                }
+            });
         }
         Expression::MultiArity(op, args) => {
-            let args: Vec<f32> = args
-                .into_iter()
-                .map(|arg| evaluate(arg, variables))
-                .collect();
-            match op {
-                Token::Max => args
-                    .into_iter()
-                    .max_by(|&a, b| a.partial_cmp(b).unwrap())
-                    .unwrap(),
-                Token::Min => args
+            let args = args
+                .iter()
+                .map(|arg| evaluate(arg, session))
+                .collect::<Result<Vec<Value>, String>>()?;
+            return lift(&op.to_string(), &args, |x| {
+                Ok(match op {
+                    Token::Max => x.iter().copied().max_by(f64::total_cmp).unwrap(),
+                    Token::Min => x.iter().copied().min_by(f64::total_cmp).unwrap(),
+                    _ => unreachable!(),
+                })
+            });
+        }
+        Expression::Call(name, args) => {
+            let args = args
+                .iter()
+                .map(|arg| evaluate(arg, session))
+                .collect::<Result<Vec<Value>, String>>()?;
+            return call(name, &args, session);
+        }
+        Expression::Assignment(t, e) => {
+            let v = evaluate(e, session)?;
+            session.set(&t.get_identifier()?, v.clone())?;
+            return Ok(v);
+        }
+        Expression::Variable(s) => return session.get(s),
+    };
+    Ok(Value::Number(value))
+}
+
+fn number(expr: &Expression, session: &mut Session) -> Result<f64, String> {
+    evaluate(expr, session)?.as_number()
+}
+
+/// Applies a function of the function registry, its arity has been checked
+/// by the parser
+fn call(name: &str, args: &[Value], session: &mut Session) -> Result<Value, String> {
+    let list = |v: &Value| -> Result<Vec<f64>, String> { vector::numbers(v.as_list()?) };
+    let numbers = |values: Vec<f64>| Value::List(values.into_iter().map(Value::Number).collect());
+    match (name, args) {
+        ("len", [v]) => return Ok(Value::Number(v.as_list()?.len() as f64)),
+        ("sum" | "prod", [Value::List(items)]) => {
+            return call(name, items, session);
+        }
+        ("dot", [a, b]) => return Ok(Value::Number(vector::dot(&list(a)?, &list(b)?)?)),
+        ("cross", [a, b]) => return Ok(numbers(vector::cross(&list(a)?, &list(b)?)?)),
+        ("norm", [v]) => return Ok(Value::Number(vector::norm(&list(v)?))),
+        ("transpose", [a]) => {
+            return Ok(matrix::to_value(matrix::transpose(&matrix::from_value(a)?)))
+        }
+        ("det", [a]) => return Ok(Value::Number(matrix::det(&matrix::from_value(a)?)?)),
+        ("inv", [a]) => return Ok(matrix::to_value(matrix::inverse(&matrix::from_value(a)?)?)),
+        ("rank", [a]) => return Ok(Value::Number(matrix::rank(&matrix::from_value(a)?) as f64)),
+        ("lu", [a]) => {
+            let lu = matrix::lu(&matrix::from_value(a)?)?;
+            return Ok(Value::List(
+                [lu.l, lu.u, lu.p]
                     .into_iter()
-                    .min_by(|&a, b| a.partial_cmp(b).unwrap())
-                    .unwrap(),
-                _ => unreachable!(),
+                    .map(matrix::to_value)
+                    .collect(),
+            ));
+        }
+        (
+            "mean" | "median" | "mode" | "var" | "pvar" | "stddev" | "pstddev" | "spread"
+            | "quartiles" | "zscore",
+            _,
+        ) => return statistics::describe(name, &statistics::values(name, args)?),
+        ("choice", [list]) => {
+            let items = list.as_list()?;
+            if items.is_empty() {
+                return Err("choice requires a list with at least one element".to_string());
             }
+            let i = session.rng().below(items.len() as u64) as usize;
+            return Ok(items[i].clone());
         }
-        Expression::Assignment(t, e) => {
-            let v = evaluate(e, variables);
-            variables.insert(t.get_identifier().unwrap(), v);
-            Default::default()
+        ("percentile", [data, p]) => {
+            let data = statistics::values(name, std::slice::from_ref(data))?;
+            return Ok(Value::Number(statistics::percentile(
+                &data,
+                p.as_number()?,
+            )?));
+        }
+        ("weekday" | "year" | "month" | "day", [value]) => {
+            let Value::Date(date) = value else {
+                return Err(format!("{name} requires a date, not {value}"));
+            };
+            let (year, month, day) = date.date();
+            return Ok(Value::Number(match name {
+                "weekday" => date.weekday() as f64,
+                "year" => year as f64,
+                "month" => month as f64,
+                _ => day as f64,
+            }));
+        }
+        ("npv", [rate, values @ ..]) => {
+            let values = statistics::values(name, values)?;
+            return Ok(Value::Number(finance::npv(rate.as_number()?, &values)?));
+        }
+        ("irr", [values, guess @ ..]) => {
+            let values = vector::numbers(values.as_list()?)?;
+            let guess = guess.first().map_or(Ok(0.1), Value::as_number)?;
+            return Ok(Value::Number(finance::irr(&values, guess)?));
+        }
+        ("xnpv", [rate, values, dates]) => {
+            return Ok(Value::Number(finance::xnpv(
+                rate.as_number()?,
+                &list(values)?,
+                dates.as_list()?,
+            )?));
+        }
+        ("amort", [rate, nper, pv]) => {
+            let nper = number_theory::natural(name, nper.as_number()?)?;
+            let rows = finance::amortization(rate.as_number()?, nper, pv.as_number()?)?;
+            return Ok(matrix::to_value(rows.into_iter().map(Vec::from).collect()));
+        }
+        ("solve", [a, b]) => {
+            let a = matrix::from_value(a)?;
+            // a vector is solved for as a single column
+            return match b.as_list().map(vector::numbers) {
+                Ok(Ok(b)) => {
+                    let b = b.into_iter().map(|x| vec![x]).collect();
+                    let x = matrix::solve(&a, &b)?;
+                    Ok(numbers(x.into_iter().map(|row| row[0]).collect()))
+                }
+                _ => Ok(matrix::to_value(matrix::solve(
+                    &a,
+                    &matrix::from_value(b)?,
+                )?)),
+            };
+        }
+        _ => {}
+    }
+    match name {
+        "rand" => Ok(Value::Number(session.rng().next_f64())),
+        "randint" => {
+            let args = vector::numbers(args)?;
+            let a = number_theory::integer(name, args[0])?;
+            let b = number_theory::integer(name, args[1])?;
+            if a > b {
+                return Err(format!("randint requires a <= b, not {a} and {b}"));
+            }
+            let n = session.rng().below((b - a) as u64 + 1);
+            Ok(Value::Number((a + n as i64) as f64))
+        }
+        "date" => {
+            let args = vector::numbers(args)?;
+            let year = number_theory::integer(name, args[0])?;
+            let month = number_theory::integer(name, args[1])?;
+            let day = number_theory::integer(name, args[2])?;
+            let days = u32::try_from(month)
+                .ok()
+                .zip(u32::try_from(day).ok())
+                .and_then(|(month, day)| dates::checked_days(year, month, day))
+                .ok_or(format!(
+                    "date({}, {}, {}) is not a valid date",
+                    args[0], args[1], args[2]
+                ))?;
+            let offset = session.settings().timezone;
+            Ok(Value::Date(DateTime::from_local(days, 0.0, offset)))
+        }
+        "range" => {
+            let args = vector::numbers(args)?;
+            let step = args.get(2).copied().unwrap_or(1.0);
+            Ok(numbers(vector::range(args[0], args[1], step)?))
+        }
+        "factor" => {
+            let n = number_theory::integer(name, args[0].as_number()?)?;
+            if n == 0 {
+                return Err("factor is not defined for 0".to_string());
+            }
+            let sign = if n < 0 {
+                vec![Value::Number(-1.0)]
+            } else {
+                vec![]
+            };
+            let primes = number_theory::factor(n.unsigned_abs());
+            let primes = primes.into_iter().map(|p| Value::Number(p as f64));
+            Ok(Value::List(sign.into_iter().chain(primes).collect()))
+        }
+        _ => {
+            let session = &*session;
+            lift(name, args, |x| number_call(name, x, session))
+        }
+    }
+}
+
+/// Functions that jump or only take whole numbers. An uncertain argument
+/// would make them jump within its uncertainty, which no derivative describes.
+const DISCRETE: &[&str] = &[
+    "floor",
+    "ceil",
+    "round",
+    "trunc",
+    "sign",
+    "factorial",
+    "nCr",
+    "nPr",
+    "gcd",
+    "lcm",
+    "isprime",
+    "nextprime",
+    "modpow",
+    "modinv",
+];
+
+/// Applies the function `name` of numbers. Uncertain arguments give an
+/// uncertain result, propagated with partial derivatives calculated
+/// numerically, and intervals give bounds of the result.
+fn lift(
+    name: &str,
+    args: &[Value],
+    f: impl Fn(&[f64]) -> Result<f64, String>,
+) -> Result<Value, String> {
+    if args.iter().any(|a| matches!(a, Value::Interval(_))) {
+        let args = args
+            .iter()
+            .map(Value::as_interval)
+            .collect::<Result<Vec<Interval>, String>>()?;
+        return interval::apply(name, &args, &f).map(Value::Interval);
+    }
+    if !args.iter().any(|a| matches!(a, Value::Uncertain(_))) {
+        let numbers = args
+            .iter()
+            .map(Value::as_number)
+            .collect::<Result<Vec<f64>, String>>()?;
+        return f(&numbers).map(Value::Number);
+    }
+    if DISCRETE.contains(&name) {
+        return Err(format!(
+            "{name} does not support uncertain values, its results jump between whole numbers"
+        ));
+    }
+    let args = args
+        .iter()
+        .map(|a| match a {
+            Value::Uncertain(u) => Ok(u.clone()),
+            _ => a.as_number().map(Uncertain::exact),
+        })
+        .collect::<Result<Vec<Uncertain>, String>>()?;
+    let values: Vec<f64> = args.iter().map(|a| a.value).collect();
+    let value = f(&values)?;
+    let mut derivatives = Vec::new();
+    for (i, arg) in args.iter().enumerate() {
+        if arg.terms.is_empty() {
+            continue;
+        }
+        let h = 1e-6 * values[i].abs().max(1e-6);
+        let at = |x: f64| {
+            let mut values = values.clone();
+            values[i] = x;
+            f(&values).ok()
+        };
+        // one-sided at the edge of the domain, e.g. sqrt(0 ± 1)
+        let derivative = match (at(values[i] + h), at(values[i] - h)) {
+            (Some(above), Some(below)) => (above - below) / (2.0 * h),
+            (Some(above), None) => (above - value) / h,
+            (None, Some(below)) => (value - below) / h,
+            (None, None) => f64::NAN,
+        };
+        if derivative.is_nan() {
+            return Err(format!(
+                "{name} has no derivative at {} to propagate the uncertainty of {arg}",
+                values[i]
+            ));
+        }
+        derivatives.push((arg, derivative));
+    }
+    Ok(Value::Uncertain(Uncertain::combine(value, &derivatives)))
+}
+
+fn number_call(name: &str, args: &[f64], session: &Session) -> Result<f64, String> {
+    let angle = session.settings().angle;
+    let value = match (name, args) {
+        ("asin", &[x]) => {
+            domain(name, x, (-1.0..=1.0).contains(&x), "-1 to 1")?;
+            angle.from_radians(x.asin())
+        }
+        ("acos", &[x]) => {
+            domain(name, x, (-1.0..=1.0).contains(&x), "-1 to 1")?;
+            angle.from_radians(x.acos())
+        }
+        ("atan", &[x]) => angle.from_radians(x.atan()),
+        ("atan2", &[y, x]) => angle.from_radians(y.atan2(x)),
+        ("sinh", &[x]) => x.sinh(),
+        ("cosh", &[x]) => x.cosh(),
+        ("tanh", &[x]) => x.tanh(),
+        ("asinh", &[x]) => x.asinh(),
+        ("acosh", &[x]) => {
+            domain(name, x, x >= 1.0, "x >= 1")?;
+            x.acosh()
+        }
+        ("atanh", &[x]) => {
+            domain(name, x, x > -1.0 && x < 1.0, "-1 < x < 1")?;
+            x.atanh()
+        }
+        ("exp", &[x]) => x.exp(),
+        ("ln", &[x]) => {
+            domain(name, x, x > 0.0, "x > 0")?;
+            x.ln()
+        }
+        ("log10", &[x]) => {
+            domain(name, x, x > 0.0, "x > 0")?;
+            x.log10()
+        }
+        ("log2", &[x]) => {
+            domain(name, x, x > 0.0, "x > 0")?;
+            x.log2()
+        }
+        ("abs", &[x]) => x.abs(),
+        ("sign", &[x]) => {
+            if x == 0.0 {
+                0.0
+            } else {
+                x.signum()
+            }
+        }
+        ("round", &[x]) => x.round(),
+        ("round", &[x, digits]) => {
+            if digits.fract() != 0.0 || digits.abs() > 15.0 {
+                return Err(format!(
+                    "round digits must be a whole number from -15 to 15, not {digits}"
+                ));
+            }
+            let scale = 10f64.powi(digits as i32);
+            (x * scale).round() / scale
+        }
+        ("trunc", &[x]) => x.trunc(),
+        ("cbrt", &[x]) => x.cbrt(),
+        ("nthroot", &[x, n]) => {
+            if n == 0.0 {
+                return Err("nthroot is not defined for n = 0".to_string());
+            }
+            let odd = n.fract() == 0.0 && n % 2.0 != 0.0;
+            domain(
+                name,
+                x,
+                x >= 0.0 || odd,
+                "x >= 0 unless n is an odd integer",
+            )?;
+            if x < 0.0 {
+                -(-x).powf(1.0 / n)
+            } else {
+                x.powf(1.0 / n)
+            }
+        }
+        ("hypot", &[a, b]) => a.hypot(b),
+        ("deg2rad", &[x]) => AngleUnit::Radians.convert(x, AngleUnit::Degrees),
+        ("rad2deg", &[x]) => AngleUnit::Degrees.convert(x, AngleUnit::Radians),
+        ("grad2rad", &[x]) => AngleUnit::Radians.convert(x, AngleUnit::Gradians),
+        ("rad2grad", &[x]) => AngleUnit::Gradians.convert(x, AngleUnit::Radians),
+        ("deg2grad", &[x]) => AngleUnit::Gradians.convert(x, AngleUnit::Degrees),
+        ("grad2deg", &[x]) => AngleUnit::Degrees.convert(x, AngleUnit::Gradians),
+        ("sum", _) => args.iter().sum(),
+        ("prod", _) => args.iter().product(),
+        (
+            "normpdf" | "normcdf" | "norminv" | "binopdf" | "binocdf" | "binoinv" | "poisspdf"
+            | "poisscdf" | "poissinv" | "unifpdf" | "unifcdf" | "unifinv" | "exppdf" | "expcdf"
+            | "expinv" | "tpdf" | "tcdf" | "tinv",
+            _,
+        ) => distributions::call(name, args)?,
+        ("pmt" | "pv" | "fv" | "nper" | "rate" | "compound", _) => finance::call(name, args)?,
+        ("factorial", &[n]) => number_theory::factorial(number_theory::natural(name, n)?)?,
+        ("nCr", &[n, r]) => number_theory::choose(
+            number_theory::natural(name, n)?,
+            number_theory::natural(name, r)?,
+        )?,
+        ("nPr", &[n, r]) => number_theory::permutations(
+            number_theory::natural(name, n)?,
+            number_theory::natural(name, r)?,
+        )?,
+        ("gcd", _) => {
+            let mut result = 0;
+            for &x in args {
+                result =
+                    number_theory::gcd(result, number_theory::integer(name, x)?.unsigned_abs());
+            }
+            result as f64
+        }
+        ("lcm", _) => {
+            let mut result = 1;
+            for &x in args {
+                let x = number_theory::integer(name, x)?.unsigned_abs();
+                result = number_theory::lcm(result, x)?;
+            }
+            result as f64
+        }
+        ("isprime", &[n]) => {
+            let n = number_theory::integer(name, n)?;
+            if n > 0 && number_theory::is_prime(n as u64) {
+                1.0
+            } else {
+                0.0
+            }
+        }
+        ("nextprime", &[n]) => {
+            let n = number_theory::integer(name, n)?;
+            number_theory::next_prime(n.max(0) as u64) as f64
+        }
+        ("modpow", &[base, exp, m]) => {
+            let base = number_theory::integer(name, base)?;
+            let exp = number_theory::natural(name, exp)?;
+            number_theory::mod_pow(base, exp, modulus(name, m)?) as f64
+        }
+        ("modinv", &[a, m]) => {
+            let a = number_theory::integer(name, a)?;
+            number_theory::mod_inv(a, modulus(name, m)?)? as f64
+        }
+        _ => return Err(format!("{name} is not a function of numbers")),
+    };
+    // catches the domain errors not checked above
+    if value.is_nan() && !args.iter().any(|a| a.is_nan()) {
+        let args: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+        return Err(format!(
+            "{}({}) is not a real number",
+            name,
+            args.join(", ")
+        ));
+    }
+    Ok(value)
+}
+
+fn modulus(name: &str, m: f64) -> Result<u64, String> {
+    let m = number_theory::integer(name, m)?;
+    if m < 1 {
+        return Err(format!("{name} modulus must be positive, not {m}"));
+    }
+    Ok(m as u64)
+}
+
+/// Gives every number of a value a unit, quantities are multiplied by it
+fn with_unit(value: &Value, unit: &Unit) -> Result<Value, String> {
+    let one = Value::Quantity(Quantity {
+        value: 1.0,
+        unit: unit.clone(),
+    });
+    match value {
+        Value::Number(n) => Ok(Value::Quantity(Quantity {
+            value: *n,
+            unit: unit.clone(),
+        })),
+        Value::Quantity(_) => Value::mul(value, &one),
+        Value::Date(_) => Err(format!("A date cannot have the unit {}", unit.name)),
+        Value::Uncertain(_) => Err(format!(
+            "Uncertain values cannot have units yet, not {}",
+            unit.name
+        )),
+        Value::Interval(i) if i.is_point() => with_unit(&Value::Number(i.lo), unit),
+        Value::Interval(_) => Err(format!(
+            "Intervals cannot have units yet, not {}",
+            unit.name
+        )),
+        Value::List(items) => Ok(Value::List(
+            items
+                .iter()
+                .map(|i| with_unit(i, unit))
+                .collect::<Result<_, _>>()?,
+        )),
+    }
+}
+
+/// Shows every number of a value as a percentage, `scale` times the value
+fn as_percent(value: &Value, scale: f64) -> Result<Value, String> {
+    match value {
+        Value::List(items) => Ok(Value::List(
+            items
+                .iter()
+                .map(|i| as_percent(i, scale))
+                .collect::<Result<_, _>>()?,
+        )),
+        _ => Ok(Value::Quantity(Quantity {
+            value: value.as_number()? * scale,
+            unit: Unit::percent(),
+        })),
+    }
+}
+
+fn convert(value: &Value, unit: &Unit) -> Result<Value, String> {
+    match value {
+        Value::Quantity(q) => Ok(Value::Quantity(q.convert(unit)?)),
+        Value::Number(_) | Value::Date(_) | Value::Uncertain(_) | Value::Interval(_) => {
+            Err(format!("Cannot convert {} to {}", value, unit.name))
+        }
+        Value::List(items) => Ok(Value::List(
+            items
+                .iter()
+                .map(|i| convert(i, unit))
+                .collect::<Result<_, _>>()?,
+        )),
+    }
+}
+
+fn domain(name: &str, x: f64, valid: bool, range: &str) -> Result<(), String> {
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("{name} is only defined for {range}, not {x}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        session::{Outcome, Session},
+        value::Value,
+    };
+
+    fn value(session: &mut Session, line: &str) -> Value {
+        match session.run(line) {
+            Ok(
+                Outcome::Value(_, v) | Outcome::Assignment(_, v) | Outcome::UnitDefinition(_, v),
+            ) => v,
+            other => panic!("{line}: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn angle_units() {
+        let mut session = Session::new();
+        assert_eq!(value(&mut session, "sin(90deg)"), Value::Number(1.0));
+        assert_eq!(value(&mut session, "cos(200grad)"), Value::Number(-1.0));
+        assert_eq!(
+            value(&mut session, "90deg"),
+            Value::Number(std::f64::consts::FRAC_PI_2)
+        );
+        session.settings_mut().set("angle", "deg").unwrap();
+        assert_eq!(value(&mut session, "sin(90)"), Value::Number(1.0));
+        assert_eq!(value(&mut session, "asin(1)"), Value::Number(90.0));
+        assert_eq!(value(&mut session, "(100 - 50)grad"), Value::Number(45.0));
+        assert_eq!(
+            value(&mut session, "rad2deg(deg2rad(30))"),
+            Value::Number(30.0)
+        );
+        assert!(session.run("acos(2)").is_err());
+        assert!(session.run("deg2rad(1, 2)").is_err());
+        assert!(session.run("nope(1)").is_err());
+    }
+
+    #[test]
+    fn scientific_functions() {
+        let mut session = Session::new();
+        assert_eq!(
+            value(&mut session, "atan2(1, 1) * 4"),
+            Value::Number(std::f64::consts::PI)
+        );
+        assert_eq!(value(&mut session, "ln(exp(2))"), Value::Number(2.0));
+        assert_eq!(
+            value(&mut session, "log10(1000) + log2(8)"),
+            Value::Number(6.0)
+        );
+        assert_eq!(
+            value(&mut session, "round(2.71828, 2)"),
+            Value::Number(2.72)
+        );
+        assert_eq!(
+            value(&mut session, "round(1250, -2)"),
+            Value::Number(1300.0)
+        );
+        assert_eq!(value(&mut session, "round(-2.5)"), Value::Number(-3.0));
+        assert_eq!(
+            value(&mut session, "trunc(-2.7) + sign(-3) + sign(0)"),
+            Value::Number(-3.0)
+        );
+        assert_eq!(
+            value(&mut session, "hypot(3, 4) + abs(-1)"),
+            Value::Number(6.0)
+        );
+        assert_eq!(value(&mut session, "cbrt(-27)"), Value::Number(-3.0));
+        assert_eq!(value(&mut session, "nthroot(-32, 5)"), Value::Number(-2.0));
+        assert_eq!(
+            value(&mut session, "tanh(0) + cosh(0) + asinh(0)"),
+            Value::Number(1.0)
+        );
+        for invalid in [
+            "ln(0)",
+            "log10(-1)",
+            "acosh(0.5)",
+            "atanh(1)",
+            "nthroot(-4, 2)",
+            "nthroot(2, 0)",
+            "sqrt(-1)",
+            "log(8, 1)",
+            "pow(-8, 0.5)",
+            "round(1, 0.5)",
+            "round(1, 2, 3)",
+        ] {
+            assert!(session.run(invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn number_theory() {
+        let mut session = Session::new();
+        assert_eq!(value(&mut session, "5!"), Value::Number(120.0));
+        assert_eq!(value(&mut session, "-3!"), Value::Number(-6.0));
+        assert_eq!(value(&mut session, "(1 + 2)!!"), Value::Number(720.0));
+        assert_eq!(
+            value(&mut session, "nCr(10, 3) + nPr(10, 3)"),
+            Value::Number(840.0)
+        );
+        assert_eq!(
+            value(&mut session, "gcd(12, 18, 30) + lcm(4, 6, 10)"),
+            Value::Number(66.0)
+        );
+        assert_eq!(
+            value(&mut session, "isprime(97) + isprime(91)"),
+            Value::Number(1.0)
+        );
+        assert_eq!(value(&mut session, "nextprime(97)"), Value::Number(101.0));
+        assert_eq!(
+            value(&mut session, "modpow(2, 100, 1000000007)"),
+            Value::Number(976371285.0)
+        );
+        assert_eq!(value(&mut session, "modinv(7, 26)"), Value::Number(15.0));
+        assert_eq!(
+            session.run("factor(-360)").unwrap(),
+            Outcome::Value(
+                10,
+                Value::List(
+                    [-1.0, 2.0, 2.0, 2.0, 3.0, 3.0, 5.0]
+                        .map(Value::Number)
+                        .to_vec()
+                )
+            )
+        );
+        for invalid in [
+            "2.5!",
+            "(-1)!",
+            "171!",
+            "!3",
+            "factor(0)",
+            "sqrt(factor(12))",
+            "gcd(1)",
+            "nCr(5.5, 2)",
+            "modinv(4, 8)",
+            "modpow(2, -1, 5)",
+            "isprime(pow(2, 60))",
+        ] {
+            assert!(session.run(invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn lists() {
+        let mut session = Session::new();
+        let list =
+            |numbers: &[f64]| Value::List(numbers.iter().map(|&n| Value::Number(n)).collect());
+        let mut result = |line: &str| value(&mut session, line);
+        assert_eq!(result("let v = [1, 2, 3]"), list(&[1.0, 2.0, 3.0]));
+        assert_eq!(result("v * 2 + 1"), list(&[3.0, 5.0, 7.0]));
+        assert_eq!(result("1 / [1, 2] - -v[0:2]"), list(&[2.0, 2.5]));
+        assert_eq!(
+            result("[[1, 2], [3, 4]] mod 3"),
+            Value::List(vec![list(&[1.0, 2.0]), list(&[0.0, 1.0])])
+        );
+        assert_eq!(result("v[0] + v[-1]"), Value::Number(4.0));
+        assert_eq!(result("v[1:]"), list(&[2.0, 3.0]));
+        assert_eq!(result("v[:-1]"), list(&[1.0, 2.0]));
+        assert_eq!(result("v[5:]"), list(&[]));
+        assert_eq!(result("range(0, 1, 0.25)"), list(&[0.0, 0.25, 0.5, 0.75]));
+        assert_eq!(result("range(3, 0, -1)"), list(&[3.0, 2.0, 1.0]));
+        assert_eq!(result("len(range(5, 10))"), Value::Number(5.0));
+        assert_eq!(
+            result("sum(v) + sum(1, 2) + prod(v) + prod([])"),
+            Value::Number(16.0)
+        );
+        assert_eq!(result("dot(v, [4, 5, 6])"), Value::Number(32.0));
+        assert_eq!(
+            result("cross([1, 0, 0], [0, 1, 0])"),
+            list(&[0.0, 0.0, 1.0])
+        );
+        assert_eq!(result("norm([3, 4])"), Value::Number(5.0));
+        for invalid in [
+            "v + [1, 2]",
+            "v[3]",
+            "v[0.5]",
+            "2[0]",
+            "sqrt(v)",
+            "[1, 2",
+            "v[1",
+            "range(0, 1, 0)",
+            "dot(v, [1])",
+            "cross([1, 2], [3, 4])",
+            "len(1)",
+        ] {
+            assert!(session.run(invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn matrices() {
+        let mut session = Session::new();
+        session.run("let M = [[1, 2], [3, 4]]").unwrap();
+        let mut result = |line: &str| session.run(line).unwrap().format(&Default::default());
+        assert_eq!(result("M @ M"), "$1 => [[7, 10], [15, 22]]");
+        assert_eq!(result("M @ [1, 1]"), "$2 => [3, 7]");
+        assert_eq!(result("[1, 1] @ M"), "$3 => [4, 6]");
+        assert_eq!(result("[1, 2] @ [3, 4]"), "$4 => 11");
+        assert_eq!(result("transpose([[1, 2, 3]])"), "$5 => [[1], [2], [3]]");
+        assert_eq!(result("det(M) + rank(M)"), "$6 => 0");
+        assert_eq!(
+            result("inv([[2, 0], [0, 4]])"),
+            "$7 => [[0.5, 0], [0, 0.25]]"
+        );
+        assert_eq!(result("solve(M, [5, 11])"), "$8 => [1, 2]");
+        assert_eq!(
+            result("lu([[1, 2], [2, 2]])"),
+            "$9 => [[[1, 0], [0.5, 1]], [[2, 2], [0, 1]], [[0, 1], [1, 0]]]"
+        );
+        for invalid in [
+            "M @ [1, 2, 3]",
+            "det([[1, 2, 3], [4, 5, 6]])",
+            "inv([[1, 2], [2, 4]])",
+            "det([[1], [2, 3]])",
+            "rank(1)",
+            "M @ 2",
+        ] {
+            assert!(session.run(invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn statistics() {
+        let mut session = Session::new();
+        session.run("let data = [2, 4, 4, 4, 5, 5, 7, 9]").unwrap();
+        let mut result = |line: &str| session.run(line).unwrap().format(&Default::default());
+        assert_eq!(result("mean(data) + mean(1, 2)"), "$1 => 6.5");
+        assert_eq!(result("median(data) + median(3, 1, 2)"), "$2 => 6.5");
+        assert_eq!(result("mode(data)"), "$3 => 4");
+        assert_eq!(result("pvar(data) + pstddev(data)"), "$4 => 6");
+        assert_eq!(
+            result("var(1, 2, 3, 4) + stddev([1, 3])"),
+            "$5 => 3.080880229039762"
+        );
+        assert_eq!(result("percentile(data, 90)"), "$6 => 7.6");
+        assert_eq!(result("quartiles(data)"), "$7 => [4, 4.5, 5.5]");
+        assert_eq!(result("spread(data)"), "$8 => 7");
+        assert_eq!(result("zscore(1, 2, 3)"), "$9 => [-1, 0, 1]");
+        for invalid in [
+            "mean([])",
+            "var(1)",
+            "median([1, [2]])",
+            "percentile(data, -1)",
+            "zscore(2, 2, 2)",
+            "range(data)",
+        ] {
+            assert!(session.run(invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn random_numbers() {
+        let mut session = Session::new();
+        crate::commands::run_command(":set seed 7", &mut session).unwrap();
+        let first: Vec<f64> = (0..5)
+            .map(|_| value(&mut session, "rand()").as_number().unwrap())
+            .collect();
+        assert!(first.iter().all(|x| (0.0..1.0).contains(x)));
+        crate::commands::run_command(":set seed 7", &mut session).unwrap();
+        let again: Vec<f64> = (0..5)
+            .map(|_| value(&mut session, "rand()").as_number().unwrap())
+            .collect();
+        assert_eq!(first, again);
+        for _ in 0..100 {
+            let n = value(&mut session, "randint(-2, 2)").as_number().unwrap();
+            assert!([-2.0, -1.0, 0.0, 1.0, 2.0].contains(&n));
+            let c = value(&mut session, "choice([3, 5])").as_number().unwrap();
+            assert!(c == 3.0 || c == 5.0);
+        }
+        assert_eq!(value(&mut session, "randint(4, 4)"), Value::Number(4.0));
+        assert_eq!(
+            value(&mut session, "binocdf(10, 10, 0.3)"),
+            Value::Number(1.0)
+        );
+        for invalid in [
+            "rand(1)",
+            "randint(2, 1)",
+            "randint(0.5, 1)",
+            "choice([])",
+            "choice(1)",
+        ] {
+            assert!(session.run(invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn units() {
+        let mut session = Session::new();
+        let mut result = |line: &str| value(&mut session, line).to_string();
+        assert_eq!(result("2 km + 500 m"), "2.5 km");
+        assert_eq!(result("10 kg * 9.81 m/s^2"), "98.10000000000001 N");
+        assert_eq!(result("60 mph in km/h"), "96.56063999999999 km/h");
+        assert_eq!(result("5 min + 30 s"), "5.5 min");
+        assert_eq!(result("1 kg m^2/s^2 to J"), "1 J");
+        assert_eq!(result("sqrt(16 m^2)"), "4 m");
+        assert_eq!(result("(3 m)^2"), "9 m^2");
+        assert_eq!(result("2 / 4 s"), "0.5 Hz");
+        assert_eq!(result("6 m / 2 m"), "3");
+        assert_eq!(result("[1, 2] km in m"), "[1000 m, 2000 m]");
+        assert_eq!(result("2^3^2 - -2^2"), "516");
+        assert_eq!(result("unit furlong = 201.168 m"), "201.168 m");
+        assert_eq!(result("3 furlong in km"), "0.603504 km");
+        assert_eq!(result("unit wafer"), "1 wafer");
+        assert_eq!(result("12 kwafer / 4"), "3 kwafer");
+        assert_eq!(result("1 kwafer in wafer"), "1000 wafer");
+        // variables and units never share a name
+        assert_eq!(result("let x = 2"), "2");
+        assert_eq!(result("3 kg in t"), "0.003 t");
+        assert_eq!(
+            session.run("3 x").unwrap_err(),
+            "x is a variable, not a unit"
+        );
+        crate::commands::run_command(":set units si", &mut session).unwrap();
+        let mut result = |line: &str| value(&mut session, line).to_string();
+        assert_eq!(result("5 furlong"), "1.00584 km");
+        assert_eq!(result("5 furlong in furlong"), "5 furlong");
+        assert_eq!(result("2 kN * 3 m"), "6 kJ");
+        for invalid in [
+            "3 m + 2 s",
+            "3 m in s",
+            "3 in m",
+            "2^(1 m)",
+            "(2 m)^0.5",
+            "3 m^x",
+            "3 m in",
+            "unit m = 2 ft",
+            "unit km = 3 m",
+            "unit kwafer = 5",
+            "unit dozen = 12",
+            "1 wafer in s",
+            "let t = 2",
+            "let km = 1",
+            "let wafer = 3",
+            "unit x",
+            "3 m in x",
+            "3 m^2147483647 * 1 m",
+            "3 m^3000000000",
+            "(3 m^1000000000)^3",
+        ] {
+            assert!(session.run(invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn percentages() {
+        let mut session = Session::new();
+        let mut result = |line: &str| value(&mut session, line).to_string();
+        assert_eq!(result("50 + 10%"), "55");
+        assert_eq!(result("50 - 10%"), "45");
+        assert_eq!(result("20% of 80"), "16");
+        assert_eq!(result("20% off 80"), "64");
+        assert_eq!(result("0.25 as %"), "25%");
+        assert_eq!(result("17 mod 5"), "2");
+        assert_eq!(result("[10, 20] + 10%"), "[11, 22]");
+        assert_eq!(result("2 km + 10%"), "2.2 km");
+        // only a percentage right after + or - changes the left side
+        assert_eq!(result("50 + (10%)"), "50.1");
+        assert_eq!(result("50 + 10% * 2"), "50.2");
+        assert_eq!(result("50 * 10%"), "5");
+        assert_eq!(result("50 + 20% of 80"), "66");
+        assert_eq!(result("10% + 50"), "50.1");
+        assert_eq!(result("-10%"), "-0.1");
+        assert_eq!(result("let share = 30% as %"), "30%");
+        assert_eq!(result("share * 80"), "24");
+        assert_eq!(result("share + 1"), "1.3");
+        for invalid in ["7 % 3", "10% (5)", "5 of 80", "20% of", "50 as", "50 as m"] {
+            assert!(session.run(invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn uncertainties() {
+        let mut session = Session::new();
+        let mut result = |line: &str| value(&mut session, line).to_string();
+        assert_eq!(result("let grav = 9.81 ± 0.02"), "9.810 ± 0.020");
+        assert_eq!(result("let time = 1.5 +/- 0.01"), "1.500 ± 0.010");
+        // the uncertainties of the same variable are correlated
+        assert_eq!(result("grav - grav"), "0 ± 0");
+        assert_eq!(result("grav + grav"), "19.620 ± 0.040");
+        assert_eq!(result("grav * grav"), "96.24 ± 0.39");
+        assert_eq!(result("grav^2"), "96.24 ± 0.39");
+        assert_eq!(result("0.5 * grav * time^2"), "11.04 ± 0.15");
+        assert_eq!(result("sqrt(4 ± 0.4)"), "2.00 ± 0.10");
+        assert_eq!(result("sin(0 ± 0.1)"), "0.00 ± 0.10");
+        assert_eq!(result("1 + 2 ± 0.5"), "3.00 ± 0.50");
+        assert_eq!(result("[1 ± 0.1, 2] * 2"), "[2.00 ± 0.20, 4]");
+        for invalid in [
+            "1 ± -1",
+            "(1 ± 0.1) m",
+            "mean([1 ± 0.1, 2])",
+            "ceil(2 ± 0.1)",
+            "round(2.5 ± 0.1)",
+            "sign(0 ± 1)",
+            "isprime(7 ± 1)",
+            "nCr(5 ± 1, 2)",
+            "gcd(12, 8 ± 1)",
+            "(5 ± 1)!",
+        ] {
+            assert!(session.run(invalid).is_err(), "{invalid}");
+        }
+        assert_eq!(
+            session.run("9.81 ± 0.02 m/s^2"),
+            Err("Uncertain values cannot have units yet, not m/s^2".to_string())
+        );
+        assert_eq!(
+            session.run("floor(2 ± 0.1)"),
+            Err(
+                "floor does not support uncertain values, its results jump between whole numbers"
+                    .to_string()
+            )
+        );
+        // a function only defined at the value has no derivative there
+        let isolated = |x: &[f64]| match x[0] {
+            1.0 => Ok(1.0),
+            x => Err(format!("not defined at {x}")),
+        };
+        let one = value(&mut session, "1 ± 0.1");
+        assert!(super::lift("isolated", &[one], isolated).is_err());
+    }
+
+    #[test]
+    fn intervals() {
+        let mut session = Session::new();
+        let mut result = |line: &str| value(&mut session, line).to_string();
+        assert_eq!(result("[1 .. 2] * [-1 .. 3]"), "[-2 .. 6]");
+        assert_eq!(result("[-1 .. 2]^2"), "[0 .. 4]");
+        assert_eq!(result("sin([0 .. 2])"), "[0 .. 1]");
+        assert_eq!(result("max([1 .. 3], 2)"), "[2 .. 3]");
+        assert_eq!(result("-[1 .. 2]"), "[-2 .. -1]");
+        assert_eq!(
+            result("sqrt([2 .. 2])"),
+            "[1.414213562373095 .. 1.4142135623730951]"
+        );
+        for invalid in [
+            "1 / [-1 .. 1]",
+            "tan([1 .. 2])",
+            "[2 .. 1]",
+            "[1 .. 2] mod 2",
+        ] {
+            assert!(session.run(invalid).is_err(), "{invalid}");
+        }
+
+        session.settings_mut().set("intervals", "on").unwrap();
+        let Ok(Outcome::Value(_, Value::Interval(sum))) = session.run("0.1 + 0.2") else {
+            panic!("0.1 + 0.2 is not an interval");
+        };
+        assert!(sum.contains(0.3) && sum.contains(0.1 + 0.2));
+        let Ok(Outcome::Value(_, Value::Interval(sine))) = session.run("sin(pi)") else {
+            panic!("sin(pi) is not an interval");
+        };
+        assert!(sine.contains(0.0) && sine.hi - sine.lo < 1e-14);
+        // exact numbers stay exact
+        assert_eq!(session.run("2 * 3").unwrap().value().to_string(), "6");
+        assert_eq!(session.run("7 mod 4").unwrap().value().to_string(), "3");
+        assert_eq!(session.run("5!").unwrap().value().to_string(), "120");
+        assert_eq!(value(&mut session, "1 ± 0.1").to_string(), "1.00 ± 0.10");
+        // what does not support intervals is an error, and a line is never
+        // evaluated a second time, which would draw another random number
+        crate::commands::run_command(":set seed 7", &mut session).unwrap();
+        let draws = [value(&mut session, "rand()"), value(&mut session, "rand()")];
+        crate::commands::run_command(":set seed 7", &mut session).unwrap();
+        assert!(session.run("rand() + 0.1 m").is_err());
+        assert_eq!(value(&mut session, "rand()"), draws[1]);
+        for (line, error) in [
+            ("0.1 m", "Intervals cannot have units yet, not m"),
+            ("pmt(0.005, 360, 200000)", "pmt does not support intervals"),
+            (
+                "25!",
+                "The result of factorial is larger than 2^53, an interval cannot hold it exactly",
+            ),
+            ("0.1 mod 3", "mod does not support intervals, in [0.09999999999999998 .. 0.10000000000000003] mod 3"),
+        ] {
+            assert_eq!(session.run(line).unwrap_err(), error);
+        }
+        assert!(session.run("mean([0.1, 0.2])").is_err());
+        assert!(session.run("1 / (0.1 - 0.1)").is_err());
+        assert!(session.run("1 / [-1 .. 1]").is_err());
+    }
+
+    #[test]
+    fn dates() {
+        let mut session = Session::new();
+        let mut result = |line: &str| value(&mut session, line).to_string();
+        assert_eq!(result("2026-12-25 - 2026-10-18"), "68 d");
+        assert_eq!(result("2026-10-18 14:30 + 90 min"), "2026-10-18 16:00");
+        assert_eq!(result("2026-03-01 - 1 d"), "2026-02-28");
+        assert_eq!(result("3d 4h"), "76 h");
+        assert_eq!(result("2026-10-18 + 1h 30min"), "2026-10-18 01:30");
+        assert_eq!(
+            result("2026-10-18T23:30Z to UTC+2"),
+            "2026-10-19 01:30 UTC+2"
+        );
+        assert_eq!(result("2026-10-18 12:00 UTC-5 - 2026-10-18 12:00"), "5 h");
+        assert_eq!(result("weekday(2026-10-18)"), "7");
+        assert_eq!(result("month(date(2024, 2, 28) + 2 d)"), "3");
+        // whole months and years follow the calendar
+        assert_eq!(result("2026-10-18 14:30 + 1 yr"), "2027-10-18 14:30");
+        assert_eq!(result("2026-01-31 + 1 month"), "2026-02-28");
+        assert_eq!(result("2024-02-29 - 1 yr"), "2023-02-28");
+        assert_eq!(result("2026-10-18 + 0.1 yr"), "2026-11-23 12:36");
+        assert_eq!(result("let v = [10, 20, 30]"), "[10, 20, 30]");
+        assert_eq!(result("v[1:3]"), "[20, 30]");
+        assert_eq!(result("today - 12:00"), "-12 h");
+        for invalid in [
+            "2026-02-30",
+            "24:00",
+            "2026-10-18 + 2026-10-18",
+            "2026-10-18 * 2",
+            "2026-10-18 + 3 m",
+            "-2026-10-18",
+            "3 to UTC+2",
+            "date(2026, 13, 1)",
+            "weekday(3)",
+            "let now = 1",
+            "[1:20]",
+        ] {
+            assert!(session.run(invalid).is_err(), "{invalid}");
         }
-        Expression::Variable(s) => *variables.get(&s).unwrap(),
     }
 }
@@ -0,0 +1,252 @@
+//! Loan and investment calculations with the conventions of spreadsheets:
+//! money paid is negative and money received positive, `rate` is the
+//! interest rate per period and `when` is 0 for payments at the end of
+//! every period or 1 for payments at its start.
+
+use crate::{dates::DateTime, value::Value};
+
+const ITERATIONS: usize = 100;
+
+fn when(name: &str, args: &[f64], i: usize) -> Result<f64, String> {
+    match args.get(i).copied().unwrap_or(0.0) {
+        w @ (0.0 | 1.0) => Ok(w),
+        w => Err(format!("{name} requires when to be 0 or 1, not {w}")),
+    }
+}
+
+fn check_rate(name: &str, rate: f64) -> Result<f64, String> {
+    if rate <= -1.0 || rate.is_nan() {
+        return Err(format!("{name} requires a rate above -1, not {rate}"));
+    }
+    Ok(rate)
+}
+
+/// The growth `(1 + rate)^nper` and the factor the payments are multiplied
+/// by in the time value of money equation
+/// `pv * growth + pmt * annuity + fv = 0`
+fn factors(rate: f64, nper: f64, when: f64) -> (f64, f64) {
+    if rate == 0.0 {
+        return (1.0, nper);
+    }
+    let growth = (1.0 + rate).powf(nper);
+    (growth, (1.0 + rate * when) * (growth - 1.0) / rate)
+}
+
+/// Finds a root of `f` close to `guess` with Newton's method
+fn solve(name: &str, f: impl Fn(f64) -> f64, guess: f64) -> Result<f64, String> {
+    let mut x = guess;
+    for _ in 0..ITERATIONS {
+        let y = f(x);
+        let h = 1e-7 * x.abs().max(1e-3);
+        let slope = (f(x + h) - f(x - h)) / (2.0 * h);
+        let next = x - y / slope;
+        if !next.is_finite() || next <= -1.0 {
+            break;
+        }
+        if (next - x).abs() <= 1e-12 * next.abs().max(1.0) {
+            return Ok(next);
+        }
+        x = next;
+    }
+    Err(format!("{name} did not converge, try another guess"))
+}
+
+/// Applies a time value of money function of the function registry
+pub fn call(name: &str, args: &[f64]) -> Result<f64, String> {
+    let arg = |i: usize| args.get(i).copied().unwrap_or(0.0);
+    let value = match name {
+        "fv" => {
+            let (rate, nper, pmt, pv) = (check_rate(name, arg(0))?, arg(1), arg(2), arg(3));
+            let (growth, annuity) = factors(rate, nper, when(name, args, 4)?);
+            -(pv * growth + pmt * annuity)
+        }
+        "pv" => {
+            let (rate, nper, pmt, fv) = (check_rate(name, arg(0))?, arg(1), arg(2), arg(3));
+            let (growth, annuity) = factors(rate, nper, when(name, args, 4)?);
+            -(fv + pmt * annuity) / growth
+        }
+        "pmt" => {
+            let (rate, nper, pv, fv) = (check_rate(name, arg(0))?, arg(1), arg(2), arg(3));
+            if nper == 0.0 {
+                return Err("pmt requires at least one period".to_string());
+            }
+            let (growth, annuity) = factors(rate, nper, when(name, args, 4)?);
+            -(fv + pv * growth) / annuity
+        }
+        "nper" => {
+            let (rate, pmt, pv, fv) = (check_rate(name, arg(0))?, arg(1), arg(2), arg(3));
+            let when = when(name, args, 4)?;
+            if rate == 0.0 {
+                -(pv + fv) / pmt
+            } else {
+                let a = pmt * (1.0 + rate * when) / rate;
+                let n = ((a - fv) / (a + pv)).ln() / rate.ln_1p();
+                if !n.is_finite() {
+                    return Err(format!(
+                        "nper: payments of {pmt} never turn {pv} into {fv} at the rate {rate}"
+                    ));
+                }
+                n
+            }
+        }
+        "rate" => {
+            let (nper, pmt, pv, fv) = (arg(0), arg(1), arg(2), arg(3));
+            let when = when(name, args, 4)?;
+            let guess = args.get(5).copied().unwrap_or(0.1);
+            let balance = |rate: f64| {
+                let (growth, annuity) = factors(rate, nper, when);
+                pv * growth + pmt * annuity + fv
+            };
+            solve(name, balance, guess)?
+        }
+        "compound" => {
+            let (principal, rate, years) = (arg(0), arg(1), arg(2));
+            let n = args.get(3).copied().unwrap_or(1.0);
+            if n <= 0.0 || n.is_nan() {
+                return Err(format!(
+                    "compound requires a positive number of periods per year, not {n}"
+                ));
+            }
+            if n.is_infinite() {
+                principal * (rate * years).exp()
+            } else {
+                principal * (1.0 + rate / n).powf(n * years)
+            }
+        }
+        _ => unreachable!(),
+    };
+    Ok(value)
+}
+
+/// The net present value of payments at the end of every period, the first
+/// one a period from now
+pub fn npv(rate: f64, values: &[f64]) -> Result<f64, String> {
+    check_rate("npv", rate)?;
+    Ok(values
+        .iter()
+        .enumerate()
+        .map(|(i, v)| v / (1.0 + rate).powi(i as i32 + 1))
+        .sum())
+}
+
+/// The rate at which payments at the start of every period, the first one
+/// now, have a net present value of 0
+pub fn irr(values: &[f64], guess: f64) -> Result<f64, String> {
+    if !(values.iter().any(|v| *v > 0.0) && values.iter().any(|v| *v < 0.0)) {
+        return Err("irr requires both positive and negative payments".to_string());
+    }
+    let npv = |rate: f64| {
+        values
+            .iter()
+            .enumerate()
+            .map(|(i, v)| v / (1.0 + rate).powi(i as i32))
+            .sum()
+    };
+    solve("irr", npv, guess)
+}
+
+/// The net present value at the first date of payments on any dates, with
+/// years of 365 days
+pub fn xnpv(rate: f64, values: &[f64], dates: &[Value]) -> Result<f64, String> {
+    check_rate("xnpv", rate)?;
+    if values.len() != dates.len() || values.is_empty() {
+        return Err(format!(
+            "xnpv requires as many dates as values, not {} and {}",
+            dates.len(),
+            values.len()
+        ));
+    }
+    let dates = dates
+        .iter()
+        .map(|d| match d {
+            Value::Date(d) => Ok(d),
+            _ => Err(format!("xnpv requires a list of dates, not {d}")),
+        })
+        .collect::<Result<Vec<&DateTime>, String>>()?;
+    Ok(values
+        .iter()
+        .zip(&dates)
+        .map(|(v, d)| v / (1.0 + rate).powf((d.seconds - dates[0].seconds) / 86400.0 / 365.0))
+        .sum())
+}
+
+/// The repayment of a loan of `pv` in `nper` equal payments at the end of
+/// every period. Every row holds the period, the payment, how much of it
+/// is interest and how much repays the loan, and the balance left.
+pub fn amortization(rate: f64, nper: u64, pv: f64) -> Result<Vec<[f64; 5]>, String> {
+    if nper == 0 || nper > 10000 {
+        return Err(format!("amort requires 1 to 10000 periods, not {nper}"));
+    }
+    let payment = -call("pmt", &[rate, nper as f64, pv])?;
+    let mut balance = pv;
+    let mut rows = Vec::new();
+    for period in 1..=nper {
+        let interest = balance * rate;
+        // the last payment repays what rounding errors left over
+        let principal = if period == nper {
+            balance
+        } else {
+            payment - interest
+        };
+        balance -= principal;
+        rows.push([
+            period as f64,
+            interest + principal,
+            interest,
+            principal,
+            balance,
+        ]);
+    }
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() <= 1e-9 * b.abs().max(1.0)
+    }
+
+    #[test]
+    fn time_value_of_money() {
+        let f = |name: &str, args: &[f64]| call(name, args).unwrap();
+        // a loan of 200000 over 30 years at 6% a year, paid monthly
+        let payment = f("pmt", &[0.005, 360.0, 200000.0]);
+        assert!(close(payment, -1199.1010503055138));
+        assert!(close(f("pv", &[0.005, 360.0, payment]), 200000.0));
+        assert!(close(f("nper", &[0.005, payment, 200000.0]), 360.0));
+        assert!(close(f("rate", &[360.0, payment, 200000.0]), 0.005));
+        assert!(close(
+            f("fv", &[0.05, 10.0, -100.0, -1000.0]),
+            2886.683880332326
+        ));
+        assert!(close(f("fv", &[0.0, 10.0, -100.0]), 1000.0));
+        assert!(close(
+            f("pmt", &[0.01, 12.0, 1000.0, 0.0, 1.0]),
+            -87.9690977013284
+        ));
+        assert!(close(
+            f("compound", &[1000.0, 0.05, 10.0, 12.0]),
+            1647.00949769028
+        ));
+        assert!(call("pmt", &[0.01, 12.0, 1000.0, 0.0, 2.0]).is_err());
+        assert!(call("nper", &[0.1, -1.0, 1000.0]).is_err());
+    }
+
+    #[test]
+    fn cash_flows() {
+        assert!(close(
+            npv(0.1, &[-10000.0, 3000.0, 4200.0, 6800.0]).unwrap(),
+            1188.4434123352207
+        ));
+        let flows = [-70000.0, 12000.0, 15000.0, 18000.0, 21000.0, 26000.0];
+        assert!(close(irr(&flows, 0.1).unwrap(), 0.08663094803653162));
+        assert!(irr(&[100.0, 200.0], 0.1).is_err());
+        let rows = amortization(0.01, 12, 1000.0).unwrap();
+        assert_eq!(rows.len(), 12);
+        assert!(close(rows[0][2], 10.0));
+        assert!(close(rows.iter().map(|r| r[3]).sum(), 1000.0));
+        assert_eq!(rows[11][4], 0.0);
+    }
+}
@@ -0,0 +1,322 @@
+//! How numbers are written: the notation, the number of digits, grouping
+//! of thousands and the decimal separator. A formula can end with a
+//! notation for its result only, e.g. `2^40 :sci 4`.
+
+use crate::units::PREFIXES;
+
+/// The most digits shown, a float holds no more than 17 significant ones
+pub const MAX_DIGITS: usize = 17;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Notation {
+    /// as many decimal places as needed, at most the precision
+    #[default]
+    Auto,
+    /// exactly as many decimal places as the precision
+    Fixed,
+    /// as many significant digits as the precision
+    Significant,
+    /// one digit before the decimal point and a power of ten, `1.2e6`
+    Scientific,
+    /// a power of ten that is a multiple of 3, `12.5e3`
+    Engineering,
+    /// an SI prefix instead of the power of ten, `4.7k`
+    Si,
+}
+
+impl Notation {
+    pub const NAMES: &'static [&'static str] = &["auto", "fix", "sig", "sci", "eng", "si"];
+
+    pub fn parse(name: &str) -> Option<Notation> {
+        Some(match name {
+            "auto" => Notation::Auto,
+            "fix" => Notation::Fixed,
+            "sig" => Notation::Significant,
+            "sci" => Notation::Scientific,
+            "eng" => Notation::Engineering,
+            "si" => Notation::Si,
+            _ => return None,
+        })
+    }
+}
+
+impl std::fmt::Display for Notation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Notation::Auto => "auto",
+            Notation::Fixed => "fix",
+            Notation::Significant => "sig",
+            Notation::Scientific => "sci",
+            Notation::Engineering => "eng",
+            Notation::Si => "si",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// The notation of a single result, `:sci 4` at the end of a formula
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Style {
+    pub notation: Notation,
+    pub digits: Option<usize>,
+}
+
+/// Splits a formula from the notation its result is shown in
+pub fn split(line: &str) -> Result<(&str, Option<Style>), String> {
+    let Some((formula, suffix)) = line.rsplit_once(':') else {
+        return Ok((line, None));
+    };
+    let mut words = suffix.split_whitespace();
+    let Some(notation) = words.next().and_then(Notation::parse) else {
+        return Ok((line, None));
+    };
+    let digits = match (words.next(), words.next()) {
+        (None, _) => None,
+        (Some(digits), None) => Some(
+            digits
+                .parse::<usize>()
+                .map_err(|_| format!("{notation} takes a number of digits, not {digits}"))?,
+        ),
+        (Some(_), Some(_)) => return Err(format!("{notation} takes a number of digits")),
+    };
+    if let Some(digits) = digits.filter(|&d| d > MAX_DIGITS) {
+        return Err(format!(
+            "{notation} shows at most {MAX_DIGITS} digits, not {digits}"
+        ));
+    }
+    Ok((formula.trim_end(), Some(Style { notation, digits })))
+}
+
+/// Writes numbers in a notation
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Format {
+    pub notation: Notation,
+    /// decimal places, or significant digits for `Significant`, all of
+    /// them when unset
+    pub digits: Option<usize>,
+    /// whether thousands are separated, by `,` or by `.` if that is not
+    /// the decimal separator
+    pub grouping: bool,
+    pub decimal: char,
+}
+
+impl Format {
+    pub fn number(&self, x: f64) -> String {
+        if !x.is_finite() {
+            return x.to_string();
+        }
+        let negative = x < 0.0;
+        let (integer, fraction, exponent) = match (self.notation, self.digits) {
+            (Notation::Auto, Some(places)) => {
+                let (integer, fraction) = fixed(x, Some(places));
+                (integer, fraction.trim_end_matches('0').to_string(), None)
+            }
+            (Notation::Fixed, Some(places)) => {
+                let (integer, fraction) = fixed(x, Some(places));
+                (integer, fraction, None)
+            }
+            (Notation::Auto | Notation::Fixed | Notation::Significant, None) => {
+                let (integer, fraction) = fixed(x, None);
+                (integer, fraction, None)
+            }
+            (Notation::Significant, Some(digits)) => {
+                let (digits, exponent) = decompose(x, Some(digits.max(1)));
+                if (-7..21).contains(&exponent) {
+                    let (integer, fraction) = positional(&digits, exponent);
+                    (integer, fraction, None)
+                } else {
+                    let (integer, fraction) = positional(&digits, 0);
+                    (integer, fraction, Some(exponent))
+                }
+            }
+            (Notation::Scientific, digits) => {
+                let (digits, exponent) = decompose(x, digits.map(|d| d + 1));
+                let (integer, fraction) = positional(&digits, 0);
+                (integer, fraction, Some(exponent))
+            }
+            (Notation::Engineering | Notation::Si, places) => {
+                let (mut digits, mut exponent) = decompose(x, None);
+                if let Some(places) = places {
+                    // the mantissa has up to 3 digits before the point
+                    let shift = exponent.rem_euclid(3) as usize;
+                    (digits, exponent) = decompose(x, Some(places + 1 + shift));
+                    // rounding up can reach the next power of ten, whose
+                    // digits are a 1 and zeros
+                    let length = places + 1 + exponent.rem_euclid(3) as usize;
+                    digits.truncate(length);
+                    while digits.len() < length {
+                        digits.push('0');
+                    }
+                }
+                let shift = exponent.rem_euclid(3);
+                let (integer, fraction) = positional(&digits, shift);
+                (integer, fraction, Some(exponent - shift))
+            }
+        };
+        let zero = integer.chars().chain(fraction.chars()).all(|c| c == '0');
+        let mut text = String::new();
+        if negative && !zero {
+            text.push('-');
+        }
+        text += &self.group(&integer);
+        if !fraction.is_empty() {
+            text.push(self.decimal);
+            text += &fraction;
+        }
+        if let Some(exponent) = exponent {
+            match prefix(exponent).filter(|_| self.notation == Notation::Si) {
+                Some(prefix) => text += prefix,
+                None => text += &format!("e{exponent}"),
+            }
+        }
+        text
+    }
+
+    fn group(&self, integer: &str) -> String {
+        if !self.grouping {
+            return integer.to_string();
+        }
+        let separator = if self.decimal == ',' { '.' } else { ',' };
+        let mut grouped = String::new();
+        for (i, c) in integer.chars().enumerate() {
+            if i > 0 && (integer.len() - i).is_multiple_of(3) {
+                grouped.push(separator);
+            }
+            grouped.push(c);
+        }
+        grouped
+    }
+}
+
+/// The integer and fractional digits of |x| with `places` decimal places,
+/// or as many as needed to read back x
+fn fixed(x: f64, places: Option<usize>) -> (String, String) {
+    let text = match places {
+        Some(places) => format!("{:.*}", places, x.abs()),
+        None => x.abs().to_string(),
+    };
+    match text.split_once('.') {
+        Some((integer, fraction)) => (integer.to_string(), fraction.to_string()),
+        None => (text, String::new()),
+    }
+}
+
+/// The decimal digits of |x| and the power of ten of the first one,
+/// rounded to `significant` digits or as few as read back as x
+fn decompose(x: f64, significant: Option<usize>) -> (String, i32) {
+    let text = match significant {
+        Some(digits) => format!("{:.*e}", digits.saturating_sub(1), x.abs()),
+        None => format!("{:e}", x.abs()),
+    };
+    let (mantissa, exponent) = text.split_once('e').unwrap();
+    (mantissa.replace('.', ""), exponent.parse().unwrap())
+}
+
+/// Places the decimal point in `digits` whose first one is at the power of
+/// ten `exponent`
+fn positional(digits: &str, exponent: i32) -> (String, String) {
+    if exponent < 0 {
+        let zeros = "0".repeat((-exponent - 1) as usize);
+        return ("0".to_string(), zeros + digits);
+    }
+    let point = exponent as usize + 1;
+    if digits.len() <= point {
+        return (
+            digits.to_string() + &"0".repeat(point - digits.len()),
+            String::new(),
+        );
+    }
+    (digits[..point].to_string(), digits[point..].to_string())
+}
+
+/// The SI prefix of a power of ten that is a multiple of 3
+fn prefix(exponent: i32) -> Option<&'static str> {
+    if exponent == 0 {
+        return Some("");
+    }
+    if exponent % 3 != 0 {
+        return None;
+    }
+    PREFIXES
+        .iter()
+        .find(|(_, factor)| factor.log10().round() as i32 == exponent)
+        .map(|(prefix, _)| *prefix)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format(notation: Notation, digits: Option<usize>) -> Format {
+        Format {
+            notation,
+            digits,
+            grouping: false,
+            decimal: '.',
+        }
+    }
+
+    #[test]
+    fn formats_notations() {
+        let sig = format(Notation::Significant, Some(3));
+        assert_eq!(sig.number(123456.0), "123000");
+        assert_eq!(sig.number(0.00012345), "0.000123");
+        assert_eq!(sig.number(2.5), "2.50");
+        assert_eq!(sig.number(9.999), "10.0");
+        assert_eq!(format(Notation::Fixed, Some(2)).number(-0.001), "0.00");
+        assert_eq!(format(Notation::Fixed, Some(2)).number(1.0 / 3.0), "0.33");
+        assert_eq!(
+            format(Notation::Scientific, Some(3)).number(123456.0),
+            "1.235e5"
+        );
+        assert_eq!(
+            format(Notation::Scientific, None).number(-0.00025),
+            "-2.5e-4"
+        );
+        let eng = format(Notation::Engineering, Some(2));
+        assert_eq!(eng.number(123456.0), "123.46e3");
+        assert_eq!(eng.number(0.0047), "4.70e-3");
+        assert_eq!(
+            format(Notation::Engineering, None).number(12500.0),
+            "12.5e3"
+        );
+        assert_eq!(format(Notation::Si, None).number(4700.0), "4.7k");
+        assert_eq!(format(Notation::Si, Some(1)).number(0.000001234), "1.2u");
+        assert_eq!(format(Notation::Si, None).number(12.0), "12");
+        assert_eq!(format(Notation::Si, None).number(1e30), "1e30");
+        assert_eq!(format(Notation::Auto, Some(3)).number(2.5), "2.5");
+    }
+
+    #[test]
+    fn groups_thousands() {
+        let mut f = format(Notation::Fixed, Some(2));
+        f.grouping = true;
+        assert_eq!(f.number(-1234567.891), "-1,234,567.89");
+        assert_eq!(f.number(123.0), "123.00");
+        f.decimal = ',';
+        assert_eq!(f.number(1234.5), "1.234,50");
+    }
+
+    #[test]
+    fn splits_styles() {
+        assert_eq!(split("1 + 2"), Ok(("1 + 2", None)));
+        assert_eq!(split("v[1:2]"), Ok(("v[1:2]", None)));
+        assert_eq!(
+            split("2^40 :sci 4"),
+            Ok((
+                "2^40",
+                Some(Style {
+                    notation: Notation::Scientific,
+                    digits: Some(4)
+                })
+            ))
+        );
+        assert_eq!(
+            split("1/3 :sig").map(|(f, s)| (f, s.unwrap().digits)),
+            Ok(("1/3", None))
+        );
+        assert!(split("1 :fix many").is_err());
+        assert!(split("1/3 :sci 17").is_ok());
+        assert!(split("1/3 :sci 100").is_err());
+    }
+}
@@ -0,0 +1,694 @@
+/// A built-in function as presented to the user, e.g. in completions
+#[derive(Debug, PartialEq)]
+pub struct Function {
+    pub name: &'static str,
+    /// parameter names, optional ones are in square brackets
+    pub params: &'static [&'static str],
+    /// accepts any number of arguments after the listed ones
+    pub variadic: bool,
+    pub description: &'static str,
+}
+
+impl Function {
+    /// The call signature, e.g. `pow(base, exp)` or `max(a, b, ...)`
+    pub fn signature(&self) -> String {
+        let mut params = self.params.join(", ");
+        if self.variadic {
+            params += ", ...";
+        }
+        format!("{}({})", self.name, params)
+    }
+
+    pub fn check_arity(&self, args: usize) -> Result<(), String> {
+        let params = self.params.len();
+        let required = self.params.iter().filter(|p| !p.starts_with('[')).count();
+        if (required..=params).contains(&args) || (self.variadic && args > params) {
+            return Ok(());
+        }
+        let expected = match (self.variadic, required, params) {
+            (true, 1, _) => "at least 1 argument".to_string(),
+            (true, r, _) => format!("at least {r} arguments"),
+            (false, 1, 1) => "1 argument".to_string(),
+            (false, r, p) if r == p => format!("{p} arguments"),
+            (false, r, p) => format!("{r} to {p} arguments"),
+        };
+        Err(format!(
+            "{} takes {}, {} given: {}",
+            self.name,
+            expected,
+            args,
+            self.signature()
+        ))
+    }
+}
+
+pub const FUNCTIONS: &[Function] = &[
+    Function {
+        name: "cos",
+        params: &["x"],
+        variadic: false,
+        description: "cosine of the angle x",
+    },
+    Function {
+        name: "sin",
+        params: &["x"],
+        variadic: false,
+        description: "sine of the angle x",
+    },
+    Function {
+        name: "tan",
+        params: &["x"],
+        variadic: false,
+        description: "tangent of the angle x",
+    },
+    Function {
+        name: "asin",
+        params: &["x"],
+        variadic: false,
+        description: "inverse sine of x, as an angle in the current unit",
+    },
+    Function {
+        name: "acos",
+        params: &["x"],
+        variadic: false,
+        description: "inverse cosine of x, as an angle in the current unit",
+    },
+    Function {
+        name: "atan",
+        params: &["x"],
+        variadic: false,
+        description: "inverse tangent of x, as an angle in the current unit",
+    },
+    Function {
+        name: "atan2",
+        params: &["y", "x"],
+        variadic: false,
+        description: "angle of the point (x, y) from the positive x axis, in the current unit",
+    },
+    Function {
+        name: "sinh",
+        params: &["x"],
+        variadic: false,
+        description: "hyperbolic sine of x",
+    },
+    Function {
+        name: "cosh",
+        params: &["x"],
+        variadic: false,
+        description: "hyperbolic cosine of x",
+    },
+    Function {
+        name: "tanh",
+        params: &["x"],
+        variadic: false,
+        description: "hyperbolic tangent of x",
+    },
+    Function {
+        name: "asinh",
+        params: &["x"],
+        variadic: false,
+        description: "inverse hyperbolic sine of x",
+    },
+    Function {
+        name: "acosh",
+        params: &["x"],
+        variadic: false,
+        description: "inverse hyperbolic cosine of x, for x >= 1",
+    },
+    Function {
+        name: "atanh",
+        params: &["x"],
+        variadic: false,
+        description: "inverse hyperbolic tangent of x, for -1 < x < 1",
+    },
+    Function {
+        name: "deg2rad",
+        params: &["x"],
+        variadic: false,
+        description: "converts x degrees to radians",
+    },
+    Function {
+        name: "rad2deg",
+        params: &["x"],
+        variadic: false,
+        description: "converts x radians to degrees",
+    },
+    Function {
+        name: "grad2rad",
+        params: &["x"],
+        variadic: false,
+        description: "converts x gradians to radians",
+    },
+    Function {
+        name: "rad2grad",
+        params: &["x"],
+        variadic: false,
+        description: "converts x radians to gradians",
+    },
+    Function {
+        name: "deg2grad",
+        params: &["x"],
+        variadic: false,
+        description: "converts x degrees to gradians",
+    },
+    Function {
+        name: "grad2deg",
+        params: &["x"],
+        variadic: false,
+        description: "converts x gradians to degrees",
+    },
+    Function {
+        name: "sqrt",
+        params: &["x"],
+        variadic: false,
+        description: "square root of x",
+    },
+    Function {
+        name: "cbrt",
+        params: &["x"],
+        variadic: false,
+        description: "cube root of x",
+    },
+    Function {
+        name: "nthroot",
+        params: &["x", "n"],
+        variadic: false,
+        description: "real n-th root of x",
+    },
+    Function {
+        name: "hypot",
+        params: &["a", "b"],
+        variadic: false,
+        description: "length of the hypotenuse, sqrt(a^2 + b^2)",
+    },
+    Function {
+        name: "floor",
+        params: &["x"],
+        variadic: false,
+        description: "largest integer less than or equal to x",
+    },
+    Function {
+        name: "ceil",
+        params: &["x"],
+        variadic: false,
+        description: "smallest integer greater than or equal to x",
+    },
+    Function {
+        name: "round",
+        params: &["x", "[digits]"],
+        variadic: false,
+        description: "x rounded to the given number of decimal digits, 0 by default",
+    },
+    Function {
+        name: "trunc",
+        params: &["x"],
+        variadic: false,
+        description: "integer part of x",
+    },
+    Function {
+        name: "abs",
+        params: &["x"],
+        variadic: false,
+        description: "absolute value of x",
+    },
+    Function {
+        name: "sign",
+        params: &["x"],
+        variadic: false,
+        description: "-1, 0 or 1 depending on the sign of x",
+    },
+    Function {
+        name: "pow",
+        params: &["base", "exp"],
+        variadic: false,
+        description: "base raised to the power of exp",
+    },
+    Function {
+        name: "log",
+        params: &["x", "base"],
+        variadic: false,
+        description: "logarithm of x to the given base",
+    },
+    Function {
+        name: "ln",
+        params: &["x"],
+        variadic: false,
+        description: "natural logarithm of x",
+    },
+    Function {
+        name: "log10",
+        params: &["x"],
+        variadic: false,
+        description: "base 10 logarithm of x",
+    },
+    Function {
+        name: "log2",
+        params: &["x"],
+        variadic: false,
+        description: "base 2 logarithm of x",
+    },
+    Function {
+        name: "exp",
+        params: &["x"],
+        variadic: false,
+        description: "e raised to the power of x",
+    },
+    Function {
+        name: "max",
+        params: &["a", "b"],
+        variadic: true,
+        description: "largest of the arguments",
+    },
+    Function {
+        name: "min",
+        params: &["a", "b"],
+        variadic: true,
+        description: "smallest of the arguments",
+    },
+    Function {
+        name: "len",
+        params: &["list"],
+        variadic: false,
+        description: "number of elements of list",
+    },
+    Function {
+        name: "range",
+        params: &["start", "stop", "[step]"],
+        variadic: false,
+        description: "list of numbers from start up to but not including stop",
+    },
+    Function {
+        name: "sum",
+        params: &["x"],
+        variadic: true,
+        description: "sum of the arguments or of the elements of a list",
+    },
+    Function {
+        name: "prod",
+        params: &["x"],
+        variadic: true,
+        description: "product of the arguments or of the elements of a list",
+    },
+    Function {
+        name: "dot",
+        params: &["a", "b"],
+        variadic: false,
+        description: "dot product of the vectors a and b",
+    },
+    Function {
+        name: "cross",
+        params: &["a", "b"],
+        variadic: false,
+        description: "cross product of the 3 dimensional vectors a and b",
+    },
+    Function {
+        name: "norm",
+        params: &["v"],
+        variadic: false,
+        description: "Euclidean length of the vector v",
+    },
+    Function {
+        name: "mean",
+        params: &["x"],
+        variadic: true,
+        description: "arithmetic mean of the arguments or of a list",
+    },
+    Function {
+        name: "median",
+        params: &["x"],
+        variadic: true,
+        description: "middle value of the arguments or of a list",
+    },
+    Function {
+        name: "mode",
+        params: &["x"],
+        variadic: true,
+        description: "most frequent value, the smallest if there are several",
+    },
+    Function {
+        name: "var",
+        params: &["x"],
+        variadic: true,
+        description: "sample variance, dividing by n - 1",
+    },
+    Function {
+        name: "pvar",
+        params: &["x"],
+        variadic: true,
+        description: "population variance, dividing by n",
+    },
+    Function {
+        name: "stddev",
+        params: &["x"],
+        variadic: true,
+        description: "sample standard deviation",
+    },
+    Function {
+        name: "pstddev",
+        params: &["x"],
+        variadic: true,
+        description: "population standard deviation",
+    },
+    Function {
+        name: "percentile",
+        params: &["data", "p"],
+        variadic: false,
+        description: "value below which p percent of the list data lie",
+    },
+    Function {
+        name: "quartiles",
+        params: &["x"],
+        variadic: true,
+        description: "list of the first, second and third quartile",
+    },
+    Function {
+        name: "spread",
+        params: &["x"],
+        variadic: true,
+        description: "range of the values, largest minus smallest",
+    },
+    Function {
+        name: "zscore",
+        params: &["x"],
+        variadic: true,
+        description: "list of the standard scores of the values",
+    },
+    Function {
+        name: "rand",
+        params: &[],
+        variadic: false,
+        description: "random number from 0 up to but not including 1",
+    },
+    Function {
+        name: "randint",
+        params: &["a", "b"],
+        variadic: false,
+        description: "random whole number from a to b",
+    },
+    Function {
+        name: "choice",
+        params: &["list"],
+        variadic: false,
+        description: "random element of list",
+    },
+    Function {
+        name: "normpdf",
+        params: &["x", "[mu]", "[sigma]"],
+        variadic: false,
+        description:
+            "normal probability density, mean mu = 0 and standard deviation sigma = 1 by default",
+    },
+    Function {
+        name: "normcdf",
+        params: &["x", "[mu]", "[sigma]"],
+        variadic: false,
+        description: "normal cumulative distribution, probability of at most x",
+    },
+    Function {
+        name: "norminv",
+        params: &["p", "[mu]", "[sigma]"],
+        variadic: false,
+        description: "inverse of normcdf, x with probability p of at most x",
+    },
+    Function {
+        name: "tpdf",
+        params: &["x", "nu"],
+        variadic: false,
+        description: "Student's t probability density with nu degrees of freedom",
+    },
+    Function {
+        name: "tcdf",
+        params: &["x", "nu"],
+        variadic: false,
+        description: "Student's t cumulative distribution",
+    },
+    Function {
+        name: "tinv",
+        params: &["p", "nu"],
+        variadic: false,
+        description: "inverse of tcdf",
+    },
+    Function {
+        name: "unifpdf",
+        params: &["x", "a", "b"],
+        variadic: false,
+        description: "continuous uniform probability density on a to b",
+    },
+    Function {
+        name: "unifcdf",
+        params: &["x", "a", "b"],
+        variadic: false,
+        description: "continuous uniform cumulative distribution",
+    },
+    Function {
+        name: "unifinv",
+        params: &["p", "a", "b"],
+        variadic: false,
+        description: "inverse of unifcdf",
+    },
+    Function {
+        name: "exppdf",
+        params: &["x", "rate"],
+        variadic: false,
+        description: "exponential probability density with the given rate",
+    },
+    Function {
+        name: "expcdf",
+        params: &["x", "rate"],
+        variadic: false,
+        description: "exponential cumulative distribution",
+    },
+    Function {
+        name: "expinv",
+        params: &["p", "rate"],
+        variadic: false,
+        description: "inverse of expcdf",
+    },
+    Function {
+        name: "binopdf",
+        params: &["k", "n", "p"],
+        variadic: false,
+        description: "binomial probability of k successes in n trials of probability p",
+    },
+    Function {
+        name: "binocdf",
+        params: &["k", "n", "p"],
+        variadic: false,
+        description: "binomial probability of at most k successes",
+    },
+    Function {
+        name: "binoinv",
+        params: &["q", "n", "p"],
+        variadic: false,
+        description: "inverse of binocdf, smallest k with probability q of at most k",
+    },
+    Function {
+        name: "poisspdf",
+        params: &["k", "lambda"],
+        variadic: false,
+        description: "Poisson probability of k events at mean rate lambda",
+    },
+    Function {
+        name: "poisscdf",
+        params: &["k", "lambda"],
+        variadic: false,
+        description: "Poisson probability of at most k events",
+    },
+    Function {
+        name: "poissinv",
+        params: &["q", "lambda"],
+        variadic: false,
+        description: "inverse of poisscdf, smallest k with probability q of at most k",
+    },
+    Function {
+        name: "transpose",
+        params: &["A"],
+        variadic: false,
+        description: "rows of A as columns",
+    },
+    Function {
+        name: "det",
+        params: &["A"],
+        variadic: false,
+        description: "determinant of the square matrix A",
+    },
+    Function {
+        name: "inv",
+        params: &["A"],
+        variadic: false,
+        description: "inverse of the square matrix A",
+    },
+    Function {
+        name: "rank",
+        params: &["A"],
+        variadic: false,
+        description: "number of linearly independent rows of A",
+    },
+    Function {
+        name: "lu",
+        params: &["A"],
+        variadic: false,
+        description: "LU decomposition [L, U, P] of A with P A = L U",
+    },
+    Function {
+        name: "solve",
+        params: &["A", "b"],
+        variadic: false,
+        description: "solution x of the linear system A x = b",
+    },
+    Function {
+        name: "factorial",
+        params: &["n"],
+        variadic: false,
+        description: "n! = 1 * 2 * ... * n, also written n!",
+    },
+    Function {
+        name: "nCr",
+        params: &["n", "r"],
+        variadic: false,
+        description: "number of ways to choose r of n items",
+    },
+    Function {
+        name: "nPr",
+        params: &["n", "r"],
+        variadic: false,
+        description: "number of ordered arrangements of r of n items",
+    },
+    Function {
+        name: "gcd",
+        params: &["a", "b"],
+        variadic: true,
+        description: "greatest common divisor of the arguments",
+    },
+    Function {
+        name: "lcm",
+        params: &["a", "b"],
+        variadic: true,
+        description: "least common multiple of the arguments",
+    },
+    Function {
+        name: "isprime",
+        params: &["n"],
+        variadic: false,
+        description: "1 if n is a prime number, else 0",
+    },
+    Function {
+        name: "nextprime",
+        params: &["n"],
+        variadic: false,
+        description: "smallest prime larger than n",
+    },
+    Function {
+        name: "factor",
+        params: &["n"],
+        variadic: false,
+        description: "list of the prime factors of n",
+    },
+    Function {
+        name: "modpow",
+        params: &["base", "exp", "m"],
+        variadic: false,
+        description: "base raised to the power of exp, modulo m",
+    },
+    Function {
+        name: "modinv",
+        params: &["a", "m"],
+        variadic: false,
+        description: "inverse of a modulo m",
+    },
+    Function {
+        name: "date",
+        params: &["year", "month", "day"],
+        variadic: false,
+        description: "the date of a day in the time zone of the session",
+    },
+    Function {
+        name: "weekday",
+        params: &["date"],
+        variadic: false,
+        description: "day of the week of date, 1 for Monday to 7 for Sunday",
+    },
+    Function {
+        name: "year",
+        params: &["date"],
+        variadic: false,
+        description: "year of date",
+    },
+    Function {
+        name: "month",
+        params: &["date"],
+        variadic: false,
+        description: "month of date, 1 to 12",
+    },
+    Function {
+        name: "day",
+        params: &["date"],
+        variadic: false,
+        description: "day of the month of date",
+    },
+    Function {
+        name: "pmt",
+        params: &["rate", "nper", "pv", "[fv]", "[when]"],
+        variadic: false,
+        description: "payment per period that turns pv into fv in nper periods",
+    },
+    Function {
+        name: "pv",
+        params: &["rate", "nper", "pmt", "[fv]", "[when]"],
+        variadic: false,
+        description: "present value of nper payments pmt and a final fv",
+    },
+    Function {
+        name: "fv",
+        params: &["rate", "nper", "pmt", "[pv]", "[when]"],
+        variadic: false,
+        description: "future value of pv and nper payments pmt",
+    },
+    Function {
+        name: "nper",
+        params: &["rate", "pmt", "pv", "[fv]", "[when]"],
+        variadic: false,
+        description: "number of periods for payments pmt to turn pv into fv",
+    },
+    Function {
+        name: "rate",
+        params: &["nper", "pmt", "pv", "[fv]", "[when]", "[guess]"],
+        variadic: false,
+        description: "interest rate per period at which pmt turns pv into fv",
+    },
+    Function {
+        name: "npv",
+        params: &["rate", "values"],
+        variadic: true,
+        description: "net present value of payments at the end of every period",
+    },
+    Function {
+        name: "irr",
+        params: &["values", "[guess]"],
+        variadic: false,
+        description: "internal rate of return of payments, the first one now",
+    },
+    Function {
+        name: "xnpv",
+        params: &["rate", "values", "dates"],
+        variadic: false,
+        description: "net present value at the first date of payments on dates",
+    },
+    Function {
+        name: "compound",
+        params: &["principal", "rate", "years", "[n]"],
+        variadic: false,
+        description: "principal with interest at a yearly rate compounded n times a year",
+    },
+    Function {
+        name: "amort",
+        params: &["rate", "nper", "pv"],
+        variadic: false,
+        description:
+            "amortization schedule, rows of period, payment, interest, principal and balance",
+    },
+];
+
+pub fn lookup(name: &str) -> Option<&'static Function> {
+    FUNCTIONS.iter().find(|f| f.name == name)
+}
@@ -0,0 +1,223 @@
+use crate::{format, functions, scanner::scan, session::Session, token::Token};
+
+const NUMBER: &str = "36";
+const OPERATOR: &str = "1";
+const FUNCTION: &str = "34";
+const KEYWORD: &str = "35";
+const VARIABLE: &str = "32";
+const UNKNOWN: &str = "4;33";
+const INVALID: &str = "1;31";
+const MATCHING: &str = "1;7";
+
+/// Colors a line with ANSI escape codes based on the scanner's tokens.
+/// Characters `tokenize` would reject and unbalanced parentheses are red, and
+/// the parenthesis pair next to the cursor is highlighted.
+pub struct Highlighter<'a> {
+    session: &'a Session,
+}
+
+impl<'a> Highlighter<'a> {
+    pub fn new(session: &'a Session) -> Highlighter<'a> {
+        Highlighter { session }
+    }
+
+    /// `cursor` is a character index into `line`
+    pub fn highlight(&self, line: &str, cursor: usize) -> String {
+        if line.starts_with(':') {
+            // the command name, followed by a formula for :ast
+            let end = line.find(char::is_whitespace).unwrap_or(line.len());
+            let (name, rest) = line.split_at(end);
+            let rest = if name == ":ast" {
+                let offset = name.chars().count();
+                self.highlight(rest, cursor.saturating_sub(offset))
+            } else {
+                rest.to_string()
+            };
+            return format!("\x1b[{KEYWORD}m{name}\x1b[0m{rest}");
+        }
+        if let Ok((formula, Some(_))) = format::split(line) {
+            // the notation of the result after the formula
+            let suffix = &line[formula.len()..];
+            let notation = suffix.trim_start();
+            let gap = &suffix[..suffix.len() - notation.len()];
+            return format!(
+                "{}{gap}\x1b[{KEYWORD}m{notation}\x1b[0m",
+                self.highlight(formula, cursor)
+            );
+        }
+        let chars: Vec<char> = line.chars().collect();
+        let mut styles: Vec<Option<&str>> = vec![None; chars.len()];
+        let scanned = scan(line);
+
+        let mut after_let = false;
+        for (i, s) in scanned.iter().enumerate() {
+            // a function name can also be a unit, `month(d)` and `3 month`
+            let call = matches!(
+                scanned.get(i + 1).map(|s| &s.token),
+                Some(Ok(Token::OpenParen))
+            );
+            let style = match &s.token {
+                Err(_) => INVALID,
+                Ok(Token::Number(_) | Token::Date(_) | Token::Time(_) | Token::Zone(_)) => NUMBER,
+                Ok(
+                    Token::Let
+                    | Token::Unit
+                    | Token::To
+                    | Token::In
+                    | Token::Mod
+                    | Token::Of
+                    | Token::Off
+                    | Token::As,
+                ) => KEYWORD,
+                Ok(Token::Deg | Token::Rad | Token::Grad) => NUMBER,
+                Ok(Token::Identifier(name))
+                    if functions::lookup(name).is_some()
+                        && (call || self.session.units().lookup(name).is_none()) =>
+                {
+                    FUNCTION
+                }
+                Ok(Token::Identifier(name))
+                    if !after_let
+                        && self.session.get(name).is_err()
+                        && self.session.units().lookup(name).is_some() =>
+                {
+                    NUMBER
+                }
+                Ok(Token::Identifier(name)) => {
+                    if after_let || self.session.get(name).is_ok() {
+                        VARIABLE
+                    } else {
+                        UNKNOWN
+                    }
+                }
+                Ok(
+                    Token::OpenParen
+                    | Token::CloseParen
+                    | Token::OpenBracket
+                    | Token::CloseBracket
+                    | Token::Comma,
+                ) => continue,
+                Ok(t) if functions::lookup(&t.to_string()).is_some() => FUNCTION,
+                Ok(_) => OPERATOR,
+            };
+            after_let = matches!(s.token, Ok(Token::Let | Token::Unit));
+            styles[s.start..s.end].fill(Some(style));
+        }
+
+        // brackets are paired like parentheses, but only with each other
+        let mut open: Vec<(usize, Token)> = Vec::new();
+        let mut pairs: Vec<(usize, usize)> = Vec::new();
+        for s in scanned.iter() {
+            let opening = match &s.token {
+                Ok(t @ (Token::OpenParen | Token::OpenBracket)) => {
+                    open.push((s.start, t.clone()));
+                    continue;
+                }
+                Ok(Token::CloseParen) => Token::OpenParen,
+                Ok(Token::CloseBracket) => Token::OpenBracket,
+                _ => continue,
+            };
+            match open.last() {
+                Some((o, t)) if *t == opening => {
+                    pairs.push((*o, s.start));
+                    open.pop();
+                }
+                _ => styles[s.start] = Some(INVALID),
+            }
+        }
+        for (o, _) in open {
+            styles[o] = Some(INVALID);
+        }
+        // prefer the parenthesis just typed over the one under the cursor
+        let near = [cursor.checked_sub(1), Some(cursor)];
+        if let Some(&(o, c)) = near
+            .iter()
+            .flatten()
+            .find_map(|&i| pairs.iter().find(|&&(o, c)| o == i || c == i))
+        {
+            styles[o] = Some(MATCHING);
+            styles[c] = Some(MATCHING);
+        }
+
+        let mut highlighted = String::new();
+        let mut current: Option<&str> = None;
+        for (c, style) in chars.iter().zip(styles) {
+            if style != current {
+                if current.is_some() {
+                    highlighted += "\x1b[0m";
+                }
+                if let Some(code) = style {
+                    highlighted += &format!("\x1b[{code}m");
+                }
+                current = style;
+            }
+            highlighted.push(*c);
+        }
+        if current.is_some() {
+            highlighted += "\x1b[0m";
+        }
+        highlighted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Highlighter;
+    use crate::session::Session;
+
+    #[test]
+    fn colors_tokens() {
+        let mut session = Session::new();
+        session.run("let a = 1").unwrap();
+        let highlighter = Highlighter::new(&session);
+        assert_eq!(
+            highlighter.highlight("a + b2 ?", 0),
+            "\x1b[32ma\x1b[0m \x1b[1m+\x1b[0m \x1b[4;33mb2\x1b[0m \x1b[1;31m?\x1b[0m"
+        );
+        assert_eq!(
+            highlighter.highlight("let x = 1.5", 0),
+            "\x1b[35mlet\x1b[0m \x1b[32mx\x1b[0m \x1b[1m=\x1b[0m \x1b[36m1.5\x1b[0m"
+        );
+        assert_eq!(
+            highlighter.highlight("2 km in m", 0),
+            "\x1b[36m2\x1b[0m \x1b[36mkm\x1b[0m \x1b[35min\x1b[0m \x1b[36mm\x1b[0m"
+        );
+        assert_eq!(
+            highlighter.highlight("month(3 month)", 0),
+            "\x1b[34mmonth\x1b[0m(\x1b[36m3\x1b[0m \x1b[36mmonth\x1b[0m)"
+        );
+    }
+
+    #[test]
+    fn colors_commands() {
+        let session = Session::new();
+        let highlighter = Highlighter::new(&session);
+        assert_eq!(
+            highlighter.highlight(":ast 2", 0),
+            "\x1b[35m:ast\x1b[0m \x1b[36m2\x1b[0m"
+        );
+        assert_eq!(highlighter.highlight(":del x", 0), "\x1b[35m:del\x1b[0m x");
+        assert_eq!(
+            highlighter.highlight("2 :sci 3", 0),
+            "\x1b[36m2\x1b[0m \x1b[35m:sci 3\x1b[0m"
+        );
+    }
+
+    #[test]
+    fn matches_parentheses() {
+        let session = Session::new();
+        let highlighter = Highlighter::new(&session);
+        assert_eq!(
+            highlighter.highlight("cos((1))", 8),
+            "\x1b[34mcos\x1b[0m\x1b[1;7m(\x1b[0m(\x1b[36m1\x1b[0m)\x1b[1;7m)\x1b[0m"
+        );
+        assert_eq!(
+            highlighter.highlight("(1", 0),
+            "\x1b[1;31m(\x1b[0m\x1b[36m1\x1b[0m"
+        );
+        assert_eq!(
+            highlighter.highlight("[(1])", 0),
+            "\x1b[1;31m[\x1b[0m(\x1b[36m1\x1b[0m\x1b[1;31m]\x1b[0m)"
+        );
+    }
+}
@@ -0,0 +1,29 @@
+/// Installs a SIGINT handler so that Ctrl-C cancels the line being typed
+/// instead of killing the REPL. The terminal already discards the pending
+/// input when it sends the signal, so the handler only moves to a fresh line.
+#[cfg(unix)]
+pub fn cancel_line_on_interrupt() {
+    use std::ffi::{c_int, c_void};
+
+    const SIGINT: c_int = 2;
+
+    extern "C" {
+        fn signal(signum: c_int, handler: extern "C" fn(c_int)) -> usize;
+        fn write(fd: c_int, buf: *const c_void, count: usize) -> isize;
+    }
+
+    extern "C" fn on_interrupt(_: c_int) {
+        let message = b"\n";
+        // write(2) is async-signal-safe, unlike println!
+        unsafe {
+            write(1, message.as_ptr() as *const c_void, message.len());
+        }
+    }
+
+    unsafe {
+        signal(SIGINT, on_interrupt);
+    }
+}
+
+#[cfg(not(unix))]
+pub fn cancel_line_on_interrupt() {}
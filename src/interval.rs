@@ -0,0 +1,469 @@
+//! Interval arithmetic with outward rounding: the bounds of a result
+//! always contain every value the operation can take for arguments within
+//! the bounds of its arguments. Addition, multiplication, division and
+//! square roots are rounded to the nearest float by the hardware, their
+//! error is recovered exactly to round the bounds in the right direction.
+//! Other functions widen their bounds by two units in the last place,
+//! which covers the error of the math library and of converting angles.
+
+use std::f64::consts::{FRAC_PI_2, PI, TAU};
+
+use crate::number_theory;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Interval {
+    pub lo: f64,
+    pub hi: f64,
+}
+
+/// Rounds a result with the known sign of its error: the exact value is
+/// `value` plus a small `error`
+fn bounds(value: f64, error: f64) -> (f64, f64) {
+    if error > 0.0 {
+        (value, value.next_up())
+    } else if error < 0.0 {
+        (value.next_down(), value)
+    } else if error == 0.0 {
+        (value, value)
+    } else {
+        widen(value)
+    }
+}
+
+/// Bounds of a result of unknown rounding
+fn widen(value: f64) -> (f64, f64) {
+    (value.next_down().next_down(), value.next_up().next_up())
+}
+
+/// Bounds of `f(x)` calculated by the math library. A 0 at 0 or 1, as in
+/// sin(0) or ln(1), is exact.
+fn library(x: f64, f: impl Fn(f64) -> Result<f64, String>) -> Result<(f64, f64), String> {
+    let y = f(x)?;
+    if y == 0.0 && (x == 0.0 || x == 1.0) {
+        return Ok((y, y));
+    }
+    Ok(widen(y))
+}
+
+fn add(a: f64, b: f64) -> (f64, f64) {
+    let sum = a + b;
+    if !sum.is_finite() {
+        return (sum.next_down(), sum.next_up());
+    }
+    // two-sum
+    let b_part = sum - a;
+    let error = (a - (sum - b_part)) + (b - b_part);
+    bounds(sum, error)
+}
+
+fn mul(a: f64, b: f64) -> (f64, f64) {
+    let product = a * b;
+    if a == 0.0 || b == 0.0 {
+        // 0 times an infinite bound of an interval is 0 as well
+        return (0.0, 0.0);
+    }
+    if !product.is_finite() || product.abs() < f64::MIN_POSITIVE {
+        return widen(product);
+    }
+    bounds(product, a.mul_add(b, -product))
+}
+
+fn div(a: f64, b: f64) -> (f64, f64) {
+    let quotient = a / b;
+    if a == 0.0 {
+        return (0.0, 0.0);
+    }
+    if !quotient.is_finite() || quotient.abs() < f64::MIN_POSITIVE {
+        return widen(quotient);
+    }
+    // a - quotient * b, exactly
+    let remainder = (-quotient).mul_add(b, a);
+    bounds(quotient, remainder * b.signum())
+}
+
+fn sqrt(x: f64) -> (f64, f64) {
+    let root = x.sqrt();
+    if !root.is_finite() || root == 0.0 {
+        return (root, root);
+    }
+    bounds(root, (-root).mul_add(root, x))
+}
+
+/// The lower and upper bound of `x^n` for `x >= 0`
+fn powi(x: f64, n: u64) -> (f64, f64) {
+    let (mut lo, mut hi) = (1.0, 1.0);
+    let (mut base_lo, mut base_hi) = (x, x);
+    let mut n = n;
+    while n > 0 {
+        if n % 2 == 1 {
+            lo = mul(lo, base_lo).0;
+            hi = mul(hi, base_hi).1;
+        }
+        base_lo = mul(base_lo, base_lo).0;
+        base_hi = mul(base_hi, base_hi).1;
+        n /= 2;
+    }
+    (lo, hi)
+}
+
+impl Interval {
+    pub fn new(lo: f64, hi: f64) -> Result<Interval, String> {
+        if lo.is_nan() || hi.is_nan() || lo > hi {
+            return Err(format!("[{lo} .. {hi}] is not an interval"));
+        }
+        Ok(Interval { lo, hi })
+    }
+
+    /// The interval of a number that was read from a decimal or rounded to
+    /// the nearest float. Numbers that are exactly what their shortest
+    /// decimal form says, like 2 or 0.5, have no width.
+    pub fn around(x: f64) -> Interval {
+        let decimal = format!("{x:.1100}");
+        let decimal = decimal.trim_end_matches('0').trim_end_matches('.');
+        let (lo, hi) = if !x.is_finite() || decimal == x.to_string() {
+            (x, x)
+        } else {
+            widen(x)
+        };
+        Interval { lo, hi }
+    }
+
+    pub fn is_point(&self) -> bool {
+        self.lo == self.hi
+    }
+
+    pub fn contains(&self, x: f64) -> bool {
+        self.lo <= x && x <= self.hi
+    }
+
+    pub fn add(&self, other: &Interval) -> Interval {
+        Interval {
+            lo: add(self.lo, other.lo).0,
+            hi: add(self.hi, other.hi).1,
+        }
+    }
+
+    pub fn neg(&self) -> Interval {
+        Interval {
+            lo: -self.hi,
+            hi: -self.lo,
+        }
+    }
+
+    pub fn sub(&self, other: &Interval) -> Interval {
+        self.add(&other.neg())
+    }
+
+    pub fn mul(&self, other: &Interval) -> Interval {
+        let products = [
+            mul(self.lo, other.lo),
+            mul(self.lo, other.hi),
+            mul(self.hi, other.lo),
+            mul(self.hi, other.hi),
+        ];
+        Interval {
+            lo: products.iter().map(|p| p.0).fold(f64::INFINITY, f64::min),
+            hi: products
+                .iter()
+                .map(|p| p.1)
+                .fold(f64::NEG_INFINITY, f64::max),
+        }
+    }
+
+    pub fn div(&self, other: &Interval) -> Result<Interval, String> {
+        if other.contains(0.0) {
+            return Err(format!("Cannot divide by {other}, it contains 0"));
+        }
+        let quotients = [
+            div(self.lo, other.lo),
+            div(self.lo, other.hi),
+            div(self.hi, other.lo),
+            div(self.hi, other.hi),
+        ];
+        Ok(Interval {
+            lo: quotients.iter().map(|q| q.0).fold(f64::INFINITY, f64::min),
+            hi: quotients
+                .iter()
+                .map(|q| q.1)
+                .fold(f64::NEG_INFINITY, f64::max),
+        })
+    }
+
+    pub fn pow(&self, exponent: &Interval) -> Result<Interval, String> {
+        let n = exponent.lo;
+        if exponent.is_point() && n.fract() == 0.0 && n.abs() <= 1e9 {
+            let power = self.powi(n.abs() as u64);
+            if n < 0.0 {
+                return Interval { lo: 1.0, hi: 1.0 }.div(&power);
+            }
+            return Ok(power);
+        }
+        if self.lo < 0.0 {
+            return Err(format!(
+                "{self}^{exponent} is not a real number for the negative values"
+            ));
+        }
+        // x^y = e^(y ln x), and y ln x is bilinear in y and ln x, so its
+        // extremes and those of x^y are at the corners
+        let corners = [
+            self.lo.powf(exponent.lo),
+            self.lo.powf(exponent.hi),
+            self.hi.powf(exponent.lo),
+            self.hi.powf(exponent.hi),
+        ];
+        if corners.iter().any(|c| c.is_nan()) {
+            return Err(format!("{self}^{exponent} is not a real number"));
+        }
+        let lo = corners.iter().copied().fold(f64::INFINITY, f64::min);
+        let hi = corners.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        Ok(Interval {
+            lo: widen(lo).0.max(0.0),
+            hi: widen(hi).1,
+        })
+    }
+
+    fn powi(&self, n: u64) -> Interval {
+        if n % 2 == 1 {
+            // odd powers are increasing
+            let lo = if self.lo < 0.0 {
+                -powi(-self.lo, n).1
+            } else {
+                powi(self.lo, n).0
+            };
+            let hi = if self.hi < 0.0 {
+                -powi(-self.hi, n).0
+            } else {
+                powi(self.hi, n).1
+            };
+            return Interval { lo, hi };
+        }
+        let near = if self.contains(0.0) {
+            0.0
+        } else {
+            self.lo.abs().min(self.hi.abs())
+        };
+        let far = self.lo.abs().max(self.hi.abs());
+        Interval {
+            lo: powi(near, n).0,
+            hi: powi(far, n).1,
+        }
+    }
+
+    pub fn sqrt(&self) -> Result<Interval, String> {
+        if self.lo < 0.0 {
+            return Err(format!("sqrt is only defined for x >= 0, not {self}"));
+        }
+        Ok(Interval {
+            lo: sqrt(self.lo).0,
+            hi: sqrt(self.hi).1,
+        })
+    }
+
+    pub fn abs(&self) -> Interval {
+        if self.contains(0.0) {
+            Interval {
+                lo: 0.0,
+                hi: self.lo.abs().max(self.hi.abs()),
+            }
+        } else if self.lo > 0.0 {
+            *self
+        } else {
+            self.neg()
+        }
+    }
+
+    /// Whether the interval may contain `offset + k * period` for a whole
+    /// number k, allowing for the rounding of the division
+    fn may_contain_periodic(&self, offset: f64, period: f64) -> bool {
+        if self.hi - self.lo >= period {
+            return true;
+        }
+        let k = |x: f64| ((x - offset) / period).floor();
+        let slack = 1e-12 * (1.0 + self.lo.abs().max(self.hi.abs()));
+        k(self.lo - slack) != k(self.hi + slack)
+    }
+
+    /// Sine or cosine of an angle in radians
+    fn trig(&self, f: fn(f64) -> f64, peak: f64) -> Interval {
+        if !self.lo.is_finite() || !self.hi.is_finite() {
+            return Interval { lo: -1.0, hi: 1.0 };
+        }
+        let a = library(self.lo, |x| Ok(f(x))).unwrap();
+        let b = library(self.hi, |x| Ok(f(x))).unwrap();
+        let mut lo = a.0.min(b.0);
+        let mut hi = a.1.max(b.1);
+        if self.may_contain_periodic(peak, TAU) {
+            hi = 1.0;
+        }
+        if self.may_contain_periodic(peak + PI, TAU) {
+            lo = -1.0;
+        }
+        Interval {
+            lo: lo.max(-1.0),
+            hi: hi.min(1.0),
+        }
+    }
+}
+
+impl std::fmt::Display for Interval {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_point() {
+            return write!(f, "{}", self.lo);
+        }
+        write!(f, "[{} .. {}]", self.lo, self.hi)
+    }
+}
+
+/// Functions that only grow, or only shrink, with their argument
+const INCREASING: &[&str] = &[
+    "exp", "ln", "log10", "log2", "cbrt", "atan", "sinh", "asinh", "tanh", "atanh", "acosh",
+    "asin", "deg2rad", "rad2deg", "grad2rad", "rad2grad", "deg2grad", "grad2deg",
+];
+const DECREASING: &[&str] = &["acos"];
+/// Rounding functions, which are exact and never decrease
+const STEPS: &[&str] = &["floor", "ceil", "round", "trunc", "sign", "nextprime"];
+/// Functions of whole numbers with exact results
+const INTEGER: &[&str] = &[
+    "factorial",
+    "gcd",
+    "lcm",
+    "isprime",
+    "modpow",
+    "modinv",
+    "nCr",
+    "nPr",
+];
+
+/// Applies a function of the function registry, or a built-in one, to
+/// intervals. `f` calculates the function of numbers, angles are in
+/// radians.
+pub fn apply(
+    name: &str,
+    args: &[Interval],
+    f: &dyn Fn(&[f64]) -> Result<f64, String>,
+) -> Result<Interval, String> {
+    let one = |x: f64| f(&[x]);
+    match (name, args) {
+        (_, [x]) if INCREASING.contains(&name) => Ok(Interval {
+            lo: library(x.lo, one)?.0,
+            hi: library(x.hi, one)?.1,
+        }),
+        (_, [x]) if DECREASING.contains(&name) => Ok(Interval {
+            lo: library(x.hi, one)?.0,
+            hi: library(x.lo, one)?.1,
+        }),
+        (_, [x]) if STEPS.contains(&name) => Ok(Interval {
+            lo: one(x.lo)?,
+            hi: one(x.hi)?,
+        }),
+        ("sqrt", [x]) => x.sqrt(),
+        ("abs", [x]) => Ok(x.abs()),
+        ("sin", [x]) => Ok(x.trig(f64::sin, FRAC_PI_2)),
+        ("cos", [x]) => Ok(x.trig(f64::cos, 0.0)),
+        ("tan", [x]) => {
+            if x.may_contain_periodic(FRAC_PI_2, PI) {
+                return Err(format!("tan is unbounded on {x}"));
+            }
+            let tan = |x: f64| Ok(x.tan());
+            Ok(Interval {
+                lo: library(x.lo, tan)?.0,
+                hi: library(x.hi, tan)?.1,
+            })
+        }
+        ("cosh", [x]) => {
+            let x = x.abs();
+            Ok(Interval {
+                lo: widen(x.lo.cosh()).0.max(1.0),
+                hi: widen(x.hi.cosh()).1,
+            })
+        }
+        ("pow", [x, y]) => x.pow(y),
+        ("log", [x, base]) => {
+            if x.lo <= 0.0 || base.lo <= 0.0 || base.contains(1.0) {
+                return Err(format!(
+                    "log requires x > 0 and a positive base other than 1, not {x} and {base}"
+                ));
+            }
+            let ln = |x: &Interval| Interval {
+                lo: library(x.lo, |x| Ok(x.ln())).unwrap().0,
+                hi: library(x.hi, |x| Ok(x.ln())).unwrap().1,
+            };
+            ln(x).div(&ln(base))
+        }
+        ("hypot", [a, b]) => a.powi(2).add(&b.powi(2)).sqrt(),
+        ("max" | "min", _) => {
+            let pick = if name == "max" { f64::max } else { f64::min };
+            Ok(Interval {
+                lo: args.iter().map(|a| a.lo).reduce(pick).unwrap(),
+                hi: args.iter().map(|a| a.hi).reduce(pick).unwrap(),
+            })
+        }
+        ("sum", _) => Ok(args
+            .iter()
+            .fold(Interval { lo: 0.0, hi: 0.0 }, |sum, a| sum.add(a))),
+        ("prod", _) => Ok(args
+            .iter()
+            .fold(Interval { lo: 1.0, hi: 1.0 }, |prod, a| prod.mul(a))),
+        _ if INTEGER.contains(&name) && args.iter().all(Interval::is_point) => {
+            let x: Vec<f64> = args.iter().map(|a| a.lo).collect();
+            let value = f(&x)?;
+            // larger results, like 25!, may have been rounded
+            if value.abs() > number_theory::MAX_EXACT {
+                return Err(format!(
+                    "The result of {name} is larger than 2^53, an interval cannot hold it exactly"
+                ));
+            }
+            Ok(Interval {
+                lo: value,
+                hi: value,
+            })
+        }
+        _ => Err(format!("{name} does not support intervals")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn interval(lo: f64, hi: f64) -> Interval {
+        Interval::new(lo, hi).unwrap()
+    }
+
+    #[test]
+    fn rounds_outwards() {
+        let tenth = Interval::around(0.1);
+        assert!(tenth.lo < 0.1 && 0.1 < tenth.hi);
+        assert!(Interval::around(0.5).is_point());
+        let sum = tenth.add(&Interval::around(0.2));
+        assert!(sum.contains(0.30000000000000004) && sum.contains(0.3));
+        let third = interval(1.0, 1.0).div(&interval(3.0, 3.0)).unwrap();
+        assert_eq!(third.hi, third.lo.next_up());
+        assert_eq!(
+            interval(2.0, 3.0).add(&interval(1.0, 1.0)),
+            interval(3.0, 4.0)
+        );
+        let root = interval(2.0, 2.0).sqrt().unwrap();
+        assert!(root.lo * root.lo < 2.0 && root.hi * root.hi > 2.0);
+    }
+
+    #[test]
+    fn bounds_functions() {
+        let x = interval(-1.0, 2.0);
+        assert_eq!(x.mul(&x), interval(-2.0, 4.0));
+        assert_eq!(x.powi(2), interval(0.0, 4.0));
+        assert_eq!(x.powi(3), interval(-1.0, 8.0));
+        assert!(interval(1.0, 2.0).div(&x).is_err());
+        let no_f = |_: &[f64]| -> Result<f64, String> { unreachable!() };
+        let sin = apply("sin", &[interval(0.0, 2.0)], &no_f).unwrap();
+        assert_eq!(sin.hi, 1.0);
+        assert_eq!(sin.lo, 0.0);
+        let cos = apply("cos", &[interval(3.0, 3.5)], &no_f).unwrap();
+        assert_eq!(cos.lo, -1.0);
+        assert!(apply("tan", &[interval(1.0, 2.0)], &no_f).is_err());
+        let ln = |x: &[f64]| Ok(x[0].ln());
+        let log = apply("ln", &[interval(1.0, std::f64::consts::E)], &ln).unwrap();
+        assert!(log.contains(0.0) && log.contains(1.0));
+        assert!(interval(-1.0, 1.0).pow(&interval(0.5, 0.5)).is_err());
+    }
+}
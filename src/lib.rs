@@ -1,4 +1,27 @@
 pub mod ast;
+pub mod commands;
+pub mod completion;
+pub mod constants;
+pub mod currency;
+pub mod dates;
+pub mod distributions;
+pub mod editor;
 pub mod evaluate;
+pub mod finance;
+pub mod format;
+pub mod functions;
+pub mod highlight;
+pub mod interrupt;
+pub mod interval;
+pub mod matrix;
+pub mod number_theory;
+pub mod random;
 pub mod scanner;
+pub mod session;
+pub mod settings;
+pub mod statistics;
 pub mod token;
+pub mod uncertainty;
+pub mod units;
+pub mod value;
+pub mod vector;
@@ -1,28 +1,103 @@
-use std::{collections::HashMap, io};
+use std::{fs, process::ExitCode};
 
-use calculator_rs::{ast::ASTParser, evaluate, scanner::tokenize};
+use calculator_rs::{
+    commands::{is_command, is_exit, run_command},
+    completion::Completer,
+    editor::{Candidate, Editor, Helper, History, Line},
+    format,
+    highlight::Highlighter,
+    interrupt,
+    session::Session,
+    settings::Settings,
+};
 
-fn main() {
-    let mut input = String::new();
-    let mut vars: HashMap<String, f32> = HashMap::new();
+struct ReplHelper<'a> {
+    completer: Completer<'a>,
+    highlighter: Highlighter<'a>,
+}
+
+impl Helper for ReplHelper<'_> {
+    fn complete(&self, line: &str) -> (usize, Vec<Candidate>) {
+        self.completer.complete(line)
+    }
+
+    fn highlight(&self, line: &str, cursor: usize) -> String {
+        self.highlighter.highlight(line, cursor)
+    }
+}
+
+fn main() -> ExitCode {
+    let mut session = Session::new();
+    let history = match History::default_path() {
+        Some(path) => History::load(path),
+        None => History::new(),
+    };
+    let mut editor = Editor::new(history);
+    if let Some(dir) = Settings::config_dir() {
+        if let Ok(config) = fs::read_to_string(dir.join("config")) {
+            for e in session.settings_mut().apply_config(&config) {
+                eprintln!("Error in {}: {e}", dir.join("config").display());
+            }
+            if let Err(e) = session.refresh_rates() {
+                eprintln!("Error in {}: {e}", dir.join("config").display());
+            }
+        }
+        if let Ok(init) = fs::read_to_string(dir.join("init")) {
+            if let Err(e) = session.run_script(&init) {
+                eprintln!("Error in {}: {e}", dir.join("init").display());
+            }
+        }
+    }
+    interrupt::cancel_line_on_interrupt();
     println!("Simple Calculator");
-    println!("To calculate, type a formula:");
+    println!(
+        "To calculate, type a formula (exit with 'exit', 'quit' or Ctrl-D, help with ':help'):"
+    );
     loop {
-        let _ = io::stdin().read_line(&mut input);
-        let tokens = tokenize(input.clone());
-        if let Err(e) = tokens {
-            println!("{e}");
-            input = String::new();
+        let helper = ReplHelper {
+            completer: Completer::new(&session),
+            highlighter: Highlighter::new(&session),
+        };
+        let input = match editor.read_line(&session.settings().prompt, &helper) {
+            Ok(Line::Input(input)) => input,
+            Ok(Line::Interrupted) => continue,
+            // Ctrl-D or the end of a piped file
+            Ok(Line::Eof) => return ExitCode::SUCCESS,
+            Err(e) => {
+                eprintln!("Error: unable to read input: {e}");
+                return ExitCode::FAILURE;
+            }
+        };
+        let line = input.trim();
+        if line.is_empty() {
             continue;
         }
-        let ast = ASTParser::create_ast(tokens.unwrap());
-        if let Err(e) = ast {
-            println!("{e}");
-            input = String::new();
+        editor.add_history(line);
+        if is_exit(line) {
+            return ExitCode::SUCCESS;
+        }
+        if is_command(line) {
+            match run_command(line, &mut session) {
+                Ok(output) => println!("{output}\n"),
+                Err(e) => println!("{e}"),
+            }
             continue;
         }
-        let result = evaluate::evaluate(ast.unwrap(), &mut vars);
-        println!("=> {result}\n");
-        input = String::new();
+        // a notation for this result only, e.g. `2^40 :sci 4`
+        let (formula, style) = match format::split(line) {
+            Ok(split) => split,
+            Err(e) => {
+                println!("{e}");
+                continue;
+            }
+        };
+        match session.run(formula) {
+            Ok(outcome) => {
+                let mut shown = vec![outcome.format(&session.settings().with_style(style))];
+                shown.extend(session.rates_note(outcome.value()));
+                println!("{}\n", shown.join(" "));
+            }
+            Err(e) => println!("{e}"),
+        }
     }
 }
@@ -0,0 +1,291 @@
+//! Linear algebra on matrices, which are lists of rows of the same length
+
+use crate::value::Value;
+
+pub type Matrix = Vec<Vec<f64>>;
+
+pub fn from_value(value: &Value) -> Result<Matrix, String> {
+    let invalid = || format!("Expected a matrix, a list of rows of the same length, not {value}");
+    let rows = value.as_list().map_err(|_| invalid())?;
+    let matrix = rows
+        .iter()
+        .map(|row| {
+            row.as_list()
+                .and_then(|row| row.iter().map(Value::as_number).collect())
+                .map_err(|_| invalid())
+        })
+        .collect::<Result<Matrix, String>>()?;
+    let columns = matrix.first().map_or(0, |row| row.len());
+    if columns == 0 || matrix.iter().any(|row| row.len() != columns) {
+        return Err(invalid());
+    }
+    Ok(matrix)
+}
+
+pub fn to_value(matrix: Matrix) -> Value {
+    Value::List(
+        matrix
+            .into_iter()
+            .map(|row| Value::List(row.into_iter().map(Value::Number).collect()))
+            .collect(),
+    )
+}
+
+fn square(name: &str, a: &Matrix) -> Result<(), String> {
+    if a.len() != a[0].len() {
+        return Err(format!(
+            "{name} requires a square matrix, not {}x{}",
+            a.len(),
+            a[0].len()
+        ));
+    }
+    Ok(())
+}
+
+/// Pivots smaller than this, relative to the largest element, count as 0
+fn tolerance(a: &Matrix) -> f64 {
+    let largest = a.iter().flatten().fold(0.0, |m: f64, x| m.max(x.abs()));
+    largest * a.len().max(a[0].len()) as f64 * f64::EPSILON
+}
+
+pub fn identity(n: usize) -> Matrix {
+    (0..n)
+        .map(|i| (0..n).map(|j| if i == j { 1.0 } else { 0.0 }).collect())
+        .collect()
+}
+
+pub fn transpose(a: &Matrix) -> Matrix {
+    (0..a[0].len())
+        .map(|j| a.iter().map(|row| row[j]).collect())
+        .collect()
+}
+
+pub fn multiply(a: &Matrix, b: &Matrix) -> Result<Matrix, String> {
+    if a[0].len() != b.len() {
+        return Err(format!(
+            "Cannot multiply a {}x{} by a {}x{} matrix",
+            a.len(),
+            a[0].len(),
+            b.len(),
+            b[0].len()
+        ));
+    }
+    Ok(a.iter()
+        .map(|row| {
+            (0..b[0].len())
+                .map(|j| row.iter().zip(b).map(|(x, b_row)| x * b_row[j]).sum())
+                .collect()
+        })
+        .collect())
+}
+
+/// The matrix product `a @ b`. A list of numbers on the left is a row
+/// vector, on the right a column vector, and the result of a product with
+/// a vector is a vector again.
+pub fn product(a: &Value, b: &Value) -> Result<Value, String> {
+    let is_vector =
+        |v: &Value| matches!(v.as_list(), Ok(items) if items.iter().all(|i| i.as_number().is_ok()));
+    let (a_vector, b_vector) = (is_vector(a), is_vector(b));
+    let a = if a_vector {
+        from_value(&Value::List(vec![a.clone()]))?
+    } else {
+        from_value(a)?
+    };
+    let b = if b_vector {
+        transpose(&from_value(&Value::List(vec![b.clone()]))?)
+    } else {
+        from_value(b)?
+    };
+    let product = multiply(&a, &b)?;
+    Ok(match (a_vector, b_vector) {
+        (true, true) => Value::Number(product[0][0]),
+        (true, false) => to_value(product).as_list()?[0].clone(),
+        (false, true) => Value::List(
+            product
+                .into_iter()
+                .map(|row| Value::Number(row[0]))
+                .collect(),
+        ),
+        (false, false) => to_value(product),
+    })
+}
+
+/// LU decomposition with partial pivoting, `p a = l u`
+pub struct Lu {
+    pub l: Matrix,
+    pub u: Matrix,
+    pub p: Matrix,
+    /// whether an odd number of rows were swapped
+    odd: bool,
+    singular: bool,
+}
+
+pub fn lu(a: &Matrix) -> Result<Lu, String> {
+    square("lu", a)?;
+    let n = a.len();
+    let tolerance = tolerance(a);
+    let mut u = a.clone();
+    let mut l = identity(n);
+    let mut p = identity(n);
+    let mut odd = false;
+    let mut singular = false;
+    for k in 0..n {
+        let pivot = (k..n)
+            .max_by(|&i, &j| u[i][k].abs().total_cmp(&u[j][k].abs()))
+            .unwrap();
+        if u[pivot][k].abs() <= tolerance {
+            // the column is 0 below the diagonal already, up to rounding
+            singular = true;
+            for row in u.iter_mut().skip(k + 1) {
+                row[k] = 0.0;
+            }
+            continue;
+        }
+        if pivot != k {
+            u.swap(k, pivot);
+            p.swap(k, pivot);
+            let (top, bottom) = l.split_at_mut(pivot);
+            top[k][..k].swap_with_slice(&mut bottom[0][..k]);
+            odd = !odd;
+        }
+        let (top, bottom) = u.split_at_mut(k + 1);
+        let pivot_row = &top[k];
+        for (i, row) in bottom.iter_mut().enumerate() {
+            let factor = row[k] / pivot_row[k];
+            l[k + 1 + i][k] = factor;
+            for (x, p) in row[k..].iter_mut().zip(&pivot_row[k..]) {
+                *x -= factor * p;
+            }
+        }
+    }
+    Ok(Lu {
+        l,
+        u,
+        p,
+        odd,
+        singular,
+    })
+}
+
+pub fn det(a: &Matrix) -> Result<f64, String> {
+    square("det", a)?;
+    let lu = lu(a)?;
+    if lu.singular {
+        return Ok(0.0);
+    }
+    let det: f64 = (0..a.len()).map(|i| lu.u[i][i]).product();
+    Ok(if lu.odd { -det } else { det })
+}
+
+/// Solves `a x = b` for every column of `b`
+pub fn solve(a: &Matrix, b: &Matrix) -> Result<Matrix, String> {
+    square("solve", a)?;
+    if b.len() != a.len() {
+        return Err(format!(
+            "solve requires a right hand side with {} rows, not {}",
+            a.len(),
+            b.len()
+        ));
+    }
+    let lu = lu(a)?;
+    if lu.singular {
+        return Err("Matrix is singular".to_string());
+    }
+    let n = a.len();
+    let pb = multiply(&lu.p, b)?;
+    let mut x = pb;
+    for column in 0..x[0].len() {
+        // forward substitution with the unit lower triangle
+        for i in 0..n {
+            for j in 0..i {
+                x[i][column] -= lu.l[i][j] * x[j][column];
+            }
+        }
+        // back substitution with the upper triangle
+        for i in (0..n).rev() {
+            for j in i + 1..n {
+                x[i][column] -= lu.u[i][j] * x[j][column];
+            }
+            x[i][column] /= lu.u[i][i];
+        }
+    }
+    Ok(x)
+}
+
+pub fn inverse(a: &Matrix) -> Result<Matrix, String> {
+    square("inv", a)?;
+    solve(a, &identity(a.len()))
+}
+
+/// Number of linearly independent rows, found by Gaussian elimination
+pub fn rank(a: &Matrix) -> usize {
+    let tolerance = tolerance(a);
+    let mut a = a.clone();
+    let mut rank = 0;
+    for k in 0..a[0].len() {
+        if rank == a.len() {
+            break;
+        }
+        let pivot = (rank..a.len())
+            .max_by(|&i, &j| a[i][k].abs().total_cmp(&a[j][k].abs()))
+            .unwrap();
+        if a[pivot][k].abs() <= tolerance {
+            continue;
+        }
+        a.swap(rank, pivot);
+        for i in rank + 1..a.len() {
+            let factor = a[i][k] / a[rank][k];
+            for j in k..a[0].len() {
+                a[i][j] -= factor * a[rank][j];
+            }
+        }
+        rank += 1;
+    }
+    rank
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decomposes_and_solves() {
+        let a = vec![
+            vec![2.0, 1.0, 1.0],
+            vec![4.0, -6.0, 0.0],
+            vec![-2.0, 7.0, 2.0],
+        ];
+        let lu = lu(&a).unwrap();
+        assert_eq!(multiply(&lu.p, &a), multiply(&lu.l, &lu.u));
+        assert_eq!(det(&a), Ok(-16.0));
+        let x = solve(&a, &vec![vec![5.0], vec![-2.0], vec![9.0]]).unwrap();
+        assert_eq!(x, vec![vec![1.0], vec![1.0], vec![2.0]]);
+        let inv = inverse(&a).unwrap();
+        let product = multiply(&a, &inv).unwrap();
+        for (i, row) in product.iter().enumerate() {
+            for (j, x) in row.iter().enumerate() {
+                let expected = if i == j { 1.0 } else { 0.0 };
+                assert!((x - expected).abs() < 1e-12, "{product:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn singular_matrices() {
+        let a = vec![
+            vec![1.0, 2.0, 3.0],
+            vec![2.0, 4.0, 6.0],
+            vec![1.0, 0.0, 1.0],
+        ];
+        assert_eq!(det(&a), Ok(0.0));
+        let lu = lu(&a).unwrap();
+        for (i, row) in lu.u.iter().enumerate() {
+            assert!(row[..i].iter().all(|&x| x == 0.0), "{:?}", lu.u);
+        }
+        assert_eq!(multiply(&lu.p, &a), multiply(&lu.l, &lu.u));
+        assert_eq!(rank(&a), 2);
+        assert!(inverse(&a).is_err());
+        assert_eq!(rank(&vec![vec![1.0, 2.0, 3.0], vec![4.0, 5.0, 6.0]]), 2);
+        assert_eq!(rank(&vec![vec![0.0, 0.0]]), 0);
+    }
+}
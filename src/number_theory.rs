@@ -0,0 +1,262 @@
+//! Combinatorics and number theory on whole numbers. Arguments are checked
+//! to be integers that an f64 holds exactly, and calculations are done in
+//! integer arithmetic so that results are exact as long as they fit too.
+
+/// 2^53, the largest integer up to which every integer is an exact f64
+pub const MAX_EXACT: f64 = 9007199254740992.0;
+
+pub fn integer(name: &str, x: f64) -> Result<i64, String> {
+    if !x.is_finite() || x.fract() != 0.0 || x.abs() > MAX_EXACT {
+        return Err(format!(
+            "{name} requires whole numbers of at most 2^53, not {x}"
+        ));
+    }
+    Ok(x as i64)
+}
+
+pub fn natural(name: &str, x: f64) -> Result<u64, String> {
+    let n = integer(name, x)?;
+    if n < 0 {
+        return Err(format!("{name} requires non-negative numbers, not {x}"));
+    }
+    Ok(n as u64)
+}
+
+pub fn factorial(n: u64) -> Result<f64, String> {
+    // 171! is larger than the largest f64
+    if n > 170 {
+        return Err(format!("{n}! is too large"));
+    }
+    // products of integers stay exact while they are representable
+    Ok((2..=n).fold(1.0, |acc, i| acc * i as f64))
+}
+
+/// Converts an integer result, which has to be at most 2^53 to be exact
+fn exact(result: Option<u128>, what: impl FnOnce() -> String) -> Result<f64, String> {
+    match result {
+        Some(result) if result <= MAX_EXACT as u128 => Ok(result as f64),
+        _ => Err(format!("{} is larger than 2^53", what())),
+    }
+}
+
+/// Number of ways to choose `r` of `n` items, ignoring their order
+pub fn choose(n: u64, r: u64) -> Result<f64, String> {
+    if r > n {
+        return Ok(0.0);
+    }
+    let k = r.min(n - r);
+    let mut result: Option<u128> = Some(1);
+    for i in 1..=k {
+        // the product of i consecutive integers is divisible by i
+        result = result
+            .and_then(|e| e.checked_mul((n - k + i) as u128))
+            .map(|e| e / i as u128);
+    }
+    exact(result, || format!("nCr({n}, {r})"))
+}
+
+/// Number of ways to arrange `r` of `n` items
+pub fn permutations(n: u64, r: u64) -> Result<f64, String> {
+    if r > n {
+        return Ok(0.0);
+    }
+    let mut result: Option<u128> = Some(1);
+    for i in (n - r + 1)..=n {
+        result = result.and_then(|e| e.checked_mul(i as u128));
+    }
+    exact(result, || format!("nPr({n}, {r})"))
+}
+
+pub fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+pub fn lcm(a: u64, b: u64) -> Result<u64, String> {
+    if a == 0 || b == 0 {
+        return Ok(0);
+    }
+    let result = (a / gcd(a, b)) as u128 * b as u128;
+    exact(Some(result), || format!("lcm({a}, {b})")).map(|result| result as u64)
+}
+
+fn mul_mod(a: u64, b: u64, m: u64) -> u64 {
+    (a as u128 * b as u128 % m as u128) as u64
+}
+
+/// `base` to the power of `exp` modulo `m`, by repeated squaring
+pub fn mod_pow(base: i64, exp: u64, m: u64) -> u64 {
+    if m == 1 {
+        return 0;
+    }
+    let mut base = base.rem_euclid(m as i64) as u64;
+    let mut exp = exp;
+    let mut result = 1;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = mul_mod(result, base, m);
+        }
+        base = mul_mod(base, base, m);
+        exp >>= 1;
+    }
+    result
+}
+
+/// The inverse of `a` modulo `m`, found with the extended Euclidean algorithm
+pub fn mod_inv(a: i64, m: u64) -> Result<u64, String> {
+    let (mut r0, mut r1) = (m as i128, (a as i128).rem_euclid(m as i128));
+    let (mut t0, mut t1) = (0i128, 1i128);
+    while r1 != 0 {
+        let q = r0 / r1;
+        (r0, r1) = (r1, r0 - q * r1);
+        (t0, t1) = (t1, t0 - q * t1);
+    }
+    if r0 != 1 {
+        return Err(format!("{a} has no inverse modulo {m}"));
+    }
+    Ok(t0.rem_euclid(m as i128) as u64)
+}
+
+/// Deterministic Miller-Rabin test, exact for all 64 bit numbers
+pub fn is_prime(n: u64) -> bool {
+    const BASES: [u64; 12] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37];
+    if n < 2 {
+        return false;
+    }
+    for p in BASES {
+        if n.is_multiple_of(p) {
+            return n == p;
+        }
+    }
+    let mut d = n - 1;
+    let mut s = 0;
+    while d.is_multiple_of(2) {
+        d /= 2;
+        s += 1;
+    }
+    'bases: for a in BASES {
+        let mut x = mod_pow(a as i64, d, n);
+        if x == 1 || x == n - 1 {
+            continue;
+        }
+        for _ in 1..s {
+            x = mul_mod(x, x, n);
+            if x == n - 1 {
+                continue 'bases;
+            }
+        }
+        return false;
+    }
+    true
+}
+
+/// The smallest prime larger than `n`
+pub fn next_prime(n: u64) -> u64 {
+    let mut candidate = n.max(1) + 1;
+    while !is_prime(candidate) {
+        candidate += 1;
+    }
+    candidate
+}
+
+/// Prime factors in ascending order, repeated according to their
+/// multiplicity. 1 has no prime factors.
+pub fn factor(n: u64) -> Vec<u64> {
+    let mut factors = Vec::new();
+    let mut n = n;
+    for p in [2, 3, 5, 7, 11, 13] {
+        while n.is_multiple_of(p) {
+            factors.push(p);
+            n /= p;
+        }
+    }
+    let mut pending = vec![n];
+    while let Some(m) = pending.pop() {
+        if m == 1 {
+            continue;
+        }
+        if is_prime(m) {
+            factors.push(m);
+            continue;
+        }
+        let d = pollard_rho(m);
+        pending.push(d);
+        pending.push(m / d);
+    }
+    factors.sort();
+    factors
+}
+
+/// Finds a non-trivial divisor of a composite number without small factors
+fn pollard_rho(n: u64) -> u64 {
+    for c in 1.. {
+        let f = |x: u64| (mul_mod(x, x, n) + c) % n;
+        let (mut x, mut y, mut d) = (2, 2, 1);
+        while d == 1 {
+            x = f(x);
+            y = f(f(y));
+            d = gcd(x.abs_diff(y), n);
+        }
+        if d != n {
+            return d;
+        }
+    }
+    unreachable!()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn combinatorics() {
+        assert_eq!(factorial(0), Ok(1.0));
+        assert_eq!(factorial(20), Ok(2432902008176640000.0));
+        assert!(factorial(171).is_err());
+        assert_eq!(choose(5, 2), Ok(10.0));
+        assert_eq!(choose(56, 28), Ok(7648690600760440.0));
+        assert_eq!(choose(3, 5), Ok(0.0));
+        assert_eq!(permutations(5, 2), Ok(20.0));
+        assert_eq!(permutations(5, 0), Ok(1.0));
+        // the largest results that are still exact, and the first that are not
+        assert_eq!(choose(9007199254740992, 1), Ok(9007199254740992.0));
+        assert!(choose(9007199254740993, 1).is_err());
+        assert!(choose(57, 28).is_err());
+        assert!(choose(60, 30).is_err());
+        assert!(choose(1000, 500).is_err());
+        assert_eq!(permutations(94906266, 2), Ok(9007199231156490.0));
+        assert!(permutations(94906267, 2).is_err());
+        assert!(permutations(100, 50).is_err());
+    }
+
+    #[test]
+    fn modular_arithmetic() {
+        assert_eq!(gcd(12, 18), 6);
+        assert_eq!(lcm(4, 6), Ok(12));
+        assert_eq!(lcm(1 << 26, 1 << 27), Ok(1 << 27));
+        assert!(lcm(94906267, 94906265).is_err());
+        assert_eq!(mod_pow(4, 13, 497), 445);
+        assert_eq!(mod_pow(-2, 3, 5), 2);
+        assert_eq!(mod_inv(3, 11), Ok(4));
+        assert_eq!(mod_inv(-3, 11), Ok(7));
+        assert!(mod_inv(6, 9).is_err());
+    }
+
+    #[test]
+    fn primes() {
+        let small: Vec<u64> = (0..30).filter(|&n| is_prime(n)).collect();
+        assert_eq!(small, [2, 3, 5, 7, 11, 13, 17, 19, 23, 29]);
+        assert!(is_prime(9007199254740881));
+        assert!(!is_prime(3215031751));
+        assert_eq!(next_prime(13), 17);
+        assert_eq!(next_prime(0), 2);
+        assert_eq!(factor(360), [2, 2, 2, 3, 3, 5]);
+        assert_eq!(factor(1), Vec::<u64>::new());
+        assert_eq!(factor(9007199254740881), [9007199254740881]);
+        assert_eq!(factor(600851475143), [71, 839, 1471, 6857]);
+        assert_eq!(factor(9007199254740991), [6361, 69431, 20394401]);
+    }
+}
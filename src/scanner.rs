@@ -1,37 +1,43 @@
 use std::num::ParseFloatError;
 
-use crate::token::Token;
+use crate::{dates, token::Token};
 
-fn match_number(i: &mut usize, chars: &Vec<char>, src: &String) -> Result<Token, ParseFloatError> {
+fn match_number(i: &mut usize, chars: &[char]) -> Result<Token, ParseFloatError> {
     let start = *i;
-    while *i + 1 < src.len() && chars[*i + 1].is_numeric() {
+    while *i + 1 < chars.len() && chars[*i + 1].is_numeric() {
         *i += 1;
     }
-    if *i + 1 < src.len() && chars[*i + 1] == '.' {
-        if *i + 2 < src.len() && chars[*i + 2].is_numeric() {
+    if *i + 2 < chars.len() && chars[*i + 1] == '.' && chars[*i + 2].is_numeric() {
+        *i += 1;
+        while *i + 1 < chars.len() && chars[*i + 1].is_numeric() {
             *i += 1;
-            while chars[*i + 1].is_numeric() {
+        }
+    }
+    // an exponent, `1e3` or `2.5E-4`
+    if matches!(chars.get(*i + 1), Some('e' | 'E')) {
+        let sign = usize::from(matches!(chars.get(*i + 2), Some('+' | '-')));
+        if chars.get(*i + 2 + sign).is_some_and(|c| c.is_ascii_digit()) {
+            *i += 2 + sign;
+            while *i + 1 < chars.len() && chars[*i + 1].is_ascii_digit() {
                 *i += 1;
             }
         }
     }
     Ok(Token::Number(
-        src.get(start..*i + 1)
-            .expect("Index out of bounds")
-            .parse::<f32>()?,
+        chars[start..*i + 1]
+            .iter()
+            .collect::<String>()
+            .parse::<f64>()?,
     ))
 }
 
-fn match_word(i: &mut usize, chars: &Vec<char>, src: &String) -> Result<Token, String> {
+fn match_word(i: &mut usize, chars: &[char]) -> Result<Token, String> {
     let start = *i;
-    while *i + 3 < src.len() && chars[*i + 1].is_ascii_alphanumeric()
-        || chars[*i + 1] == '_'
-        || chars[*i + 1] == '-'
-    {
+    while *i + 1 < chars.len() && (chars[*i + 1].is_ascii_alphanumeric() || chars[*i + 1] == '_') {
         *i += 1;
     }
-    let word = src.get(start..*i + 1).expect("Index out of bounds");
-    match word {
+    let word: String = chars[start..*i + 1].iter().collect();
+    match word.as_str() {
         "max" => Ok(Token::Max),
         "min" => Ok(Token::Min),
         "sqrt" => Ok(Token::Sqrt),
@@ -43,47 +49,282 @@ fn match_word(i: &mut usize, chars: &Vec<char>, src: &String) -> Result<Token, S
         "floor" => Ok(Token::Floor),
         "ceil" => Ok(Token::Ceil),
         "let" => Ok(Token::Let),
-        _ => Ok(Token::Identifier(word.to_string())),
+        "deg" => Ok(Token::Deg),
+        "rad" => Ok(Token::Rad),
+        "grad" => Ok(Token::Grad),
+        "to" => Ok(Token::To),
+        "in" => Ok(Token::In),
+        "unit" => Ok(Token::Unit),
+        "mod" => Ok(Token::Mod),
+        "of" => Ok(Token::Of),
+        "off" => Ok(Token::Off),
+        "as" => Ok(Token::As),
+        _ => Ok(Token::Identifier(word)),
     }
 }
 
-pub fn tokenize(src: String) -> Result<Vec<Token>, String> {
+/// Reads `count` digits at `i` as a number
+fn digits(chars: &[char], i: usize, count: usize) -> Option<u32> {
+    let digits = chars.get(i..i + count)?;
+    if !digits.iter().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    digits.iter().collect::<String>().parse().ok()
+}
+
+/// Matches a date `2026-10-18` at `i`, returning its token and the index
+/// after it
+fn match_date(i: usize, chars: &[char]) -> Option<(Result<Token, String>, usize)> {
+    let year = digits(chars, i, 4)?;
+    if chars.get(i + 4) != Some(&'-') || chars.get(i + 7) != Some(&'-') {
+        return None;
+    }
+    let (month, day) = (digits(chars, i + 5, 2)?, digits(chars, i + 8, 2)?);
+    if chars.get(i + 10).is_some_and(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let token = dates::checked_days(year as i64, month, day)
+        .map(Token::Date)
+        .ok_or(format!("Invalid date at position {}", i));
+    Some((token, i + 10))
+}
+
+/// Matches a time `14:30`, `9:05:30` or `14:30:15.5` at `i`, returning its
+/// token and the index after it
+fn match_time(i: usize, chars: &[char]) -> Option<(Result<Token, String>, usize)> {
+    let hour_digits = if chars.get(i + 1).is_some_and(|c| c.is_ascii_digit()) {
+        2
+    } else {
+        1
+    };
+    let hours = digits(chars, i, hour_digits)?;
+    let mut end = i + hour_digits;
+    if chars.get(end) != Some(&':') {
+        return None;
+    }
+    let minutes = digits(chars, end + 1, 2)?;
+    end += 3;
+    let mut seconds = 0.0;
+    if chars.get(end) == Some(&':') {
+        if let Some(whole) = digits(chars, end + 1, 2) {
+            end += 3;
+            let start = end;
+            if chars.get(end) == Some(&'.') {
+                end += 1;
+                while chars.get(end).is_some_and(|c| c.is_ascii_digit()) {
+                    end += 1;
+                }
+            }
+            let fraction: String = chars[start..end].iter().collect();
+            seconds = whole as f64 + format!("0{fraction}").parse::<f64>().unwrap_or(0.0);
+        }
+    }
+    if chars.get(end).is_some_and(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let token = if hours < 24 && minutes < 60 && seconds < 60.0 {
+        Ok(Token::Time((hours * 3600 + minutes * 60) as f64 + seconds))
+    } else {
+        Err(format!("Invalid time at position {}", i))
+    };
+    Some((token, end))
+}
+
+/// Matches a time zone offset right after a time or `UTC`: `Z` after an
+/// ISO 8601 time, `+02:00` or `+0200`, and after `UTC` also `+2`
+fn match_offset(i: usize, chars: &[char], after_utc: bool) -> Option<(i32, usize)> {
+    if !after_utc && chars.get(i) == Some(&'Z') {
+        return Some((0, i + 1));
+    }
+    let end = (i + 1..chars.len())
+        .find(|&j| !(chars[j].is_ascii_digit() || chars[j] == ':'))
+        .unwrap_or(chars.len());
+    let offset: String = chars.get(i..end)?.iter().collect();
+    let iso = offset.len() == 5 || offset.len() == 6 && offset.contains(':');
+    if !(after_utc || iso) {
+        return None;
+    }
+    Some((dates::parse_offset(&offset)?, end))
+}
+
+/// A scanned token, or the reason it was rejected, together with the range
+/// of characters in the source it covers
+#[derive(Debug, PartialEq)]
+pub struct Scanned {
+    pub token: Result<Token, String>,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Scans the whole source without stopping at invalid characters, so that
+/// partial input can be inspected, e.g. for highlighting
+pub fn scan(src: &str) -> Vec<Scanned> {
     let mut i = 0;
     let chars: Vec<char> = src.chars().collect();
-    let mut tokens: Vec<Token> = Vec::new();
-    while i < src.len() {
+    let mut scanned: Vec<Scanned> = Vec::new();
+    // whether each open bracket is a square one, inside which `1:2` is a
+    // slice, or an error, rather than a time
+    let mut brackets: Vec<bool> = Vec::new();
+    // a time follows the T of an ISO 8601 date
+    let mut iso_time = false;
+    while i < chars.len() {
+        let start = i;
         let c = chars[i];
-        match c {
-            '*' => tokens.push(Token::Star),
-            '-' => tokens.push(Token::Minus),
-            '/' => tokens.push(Token::Slash),
-            '+' => tokens.push(Token::Plus),
-            '(' => tokens.push(Token::OpenParen),
-            ')' => tokens.push(Token::CloseParen),
-            ',' => tokens.push(Token::Comma),
-            '%' => tokens.push(Token::Percent),
-            '=' => tokens.push(Token::Equal),
-            ' ' => {}
-            '\n' => {}
+        let after_date = matches!(scanned.last().map(|s| &s.token), Some(Ok(Token::Date(_))));
+        let token = match c {
+            '*' => Ok(Token::Star),
+            '-' => Ok(Token::Minus),
+            '/' => Ok(Token::Slash),
+            '+' if chars.get(i + 1..i + 3) == Some(&['/', '-'][..]) => {
+                i += 2;
+                Ok(Token::PlusMinus)
+            }
+            '+' => Ok(Token::Plus),
+            '±' => Ok(Token::PlusMinus),
+            '.' if chars.get(i + 1) == Some(&'.') => {
+                i += 1;
+                Ok(Token::DotDot)
+            }
+            '(' => {
+                brackets.push(false);
+                Ok(Token::OpenParen)
+            }
+            ')' => {
+                brackets.pop();
+                Ok(Token::CloseParen)
+            }
+            ',' => Ok(Token::Comma),
+            '%' => Ok(Token::Percent),
+            '=' => Ok(Token::Equal),
+            '!' => Ok(Token::Bang),
+            '[' => {
+                brackets.push(true);
+                Ok(Token::OpenBracket)
+            }
+            ']' => {
+                brackets.pop();
+                Ok(Token::CloseBracket)
+            }
+            ':' => Ok(Token::Colon),
+            '@' => Ok(Token::At),
+            '^' => Ok(Token::Caret),
+            ' ' | '\n' | '\r' | '\t' => {
+                i += 1;
+                continue;
+            }
             _ => {
-                if c.is_numeric() {
-                    let num = match_number(&mut i, &chars, &src);
-                    match num {
-                        Ok(n) => tokens.push(n),
-                        Err(e) => return Err(e.to_string()),
+                let in_brackets = brackets.last() == Some(&true);
+                let date = match_date(i, &chars);
+                let time = match_time(i, &chars).filter(|_| iso_time || after_date || !in_brackets);
+                if let Some((token, end)) = date {
+                    iso_time =
+                        chars.get(end) == Some(&'T') && match_time(end + 1, &chars).is_some();
+                    scanned.push(Scanned { token, start, end });
+                    // the T is not part of any token
+                    i = if iso_time { end + 1 } else { end };
+                    continue;
+                } else if let Some((token, end)) = time {
+                    scanned.push(Scanned { token, start, end });
+                    if let Some((offset, zone_end)) =
+                        match_offset(end, &chars, false).filter(|_| iso_time || chars[end] == 'Z')
+                    {
+                        scanned.push(Scanned {
+                            token: Ok(Token::Zone(offset)),
+                            start: end,
+                            end: zone_end,
+                        });
+                        i = zone_end;
+                    } else {
+                        i = end;
                     }
-                } else if c.is_ascii_alphabetic() {
-                    let word = match_word(&mut i, &chars, &src);
-                    match word {
-                        Ok(w) => tokens.push(w),
-                        Err(e) => return Err(e.to_string()),
+                    iso_time = false;
+                    continue;
+                } else if c.is_numeric() {
+                    match_number(&mut i, &chars).map_err(|e| e.to_string())
+                } else if c.is_ascii_alphabetic()
+                    || c == '_'
+                    || (c == '$' && chars.get(i + 1).is_some_and(|d| d.is_ascii_digit()))
+                {
+                    match match_word(&mut i, &chars) {
+                        Ok(Token::Identifier(word)) if word == "UTC" => {
+                            match match_offset(i + 1, &chars, true) {
+                                Some((offset, end)) => {
+                                    i = end - 1;
+                                    Ok(Token::Zone(offset))
+                                }
+                                None => Ok(Token::Zone(0)),
+                            }
+                        }
+                        token => token,
                     }
                 } else {
-                    return Err(format!("Invalid character {} at position {}", c, i));
+                    Err(format!("Invalid character {} at position {}", c, i))
                 }
             }
-        }
+        };
         i += 1;
+        scanned.push(Scanned {
+            token,
+            start,
+            end: i,
+        });
+    }
+    scanned
+}
+
+pub fn tokenize(src: String) -> Result<Vec<Token>, String> {
+    let mut tokens: Vec<Token> = Vec::new();
+    for scanned in scan(&src) {
+        let token = scanned.token?;
+        // a factorial needs something to apply to
+        if token == Token::Bang
+            && !matches!(
+                tokens.last(),
+                Some(
+                    Token::Number(_)
+                        | Token::Identifier(_)
+                        | Token::CloseParen
+                        | Token::CloseBracket
+                        | Token::Bang
+                        | Token::Deg
+                        | Token::Rad
+                        | Token::Grad
+                )
+            )
+        {
+            return Err(format!(
+                "'!' at position {} must follow a value",
+                scanned.start
+            ));
+        }
+        tokens.push(token);
+    }
+    if let Some(last) = tokens.last() {
+        if [
+            Token::Plus,
+            Token::Minus,
+            Token::PlusMinus,
+            Token::DotDot,
+            Token::Star,
+            Token::Slash,
+            Token::Mod,
+            Token::Of,
+            Token::Off,
+            Token::As,
+            Token::At,
+            Token::Caret,
+            Token::To,
+            Token::In,
+            Token::Comma,
+            Token::Equal,
+            Token::OpenParen,
+            Token::OpenBracket,
+            Token::Colon,
+        ]
+        .contains(last)
+        {
+            return Err(format!("Formula cannot end with '{}'", last));
+        }
     }
     Ok(tokens)
 }
@@ -160,6 +401,28 @@ mod tests {
                     Token::Number(2.0),
                 ],
             ),
+            (
+                "1e3 + 2.5E-4 - 1e+2",
+                vec![
+                    Token::Number(1000.0),
+                    Token::Plus,
+                    Token::Number(0.00025),
+                    Token::Minus,
+                    Token::Number(100.0),
+                ],
+            ),
+            (
+                "2e",
+                vec![Token::Number(2.0), Token::Identifier("e".to_string())],
+            ),
+            (
+                "a-b",
+                vec![
+                    Token::Identifier("a".to_string()),
+                    Token::Minus,
+                    Token::Identifier("b".to_string()),
+                ],
+            ),
         ];
         for case in testcases.iter() {
             let res = super::tokenize(case.0.to_string());
@@ -175,6 +438,62 @@ mod tests {
         }
     }
 
+    #[test]
+    fn dates_and_times() {
+        let tokens = |src: &str| super::tokenize(src.to_string()).unwrap();
+        assert_eq!(
+            tokens("2026-10-18 14:30 UTC+2"),
+            vec![Token::Date(20744), Token::Time(52200.0), Token::Zone(120)]
+        );
+        assert_eq!(
+            tokens("2026-10-18T09:05:30.5-05:30"),
+            vec![Token::Date(20744), Token::Time(32730.5), Token::Zone(-330)]
+        );
+        assert_eq!(
+            tokens("12:00Z - 1"),
+            vec![
+                Token::Time(43200.0),
+                Token::Zone(0),
+                Token::Minus,
+                Token::Number(1.0)
+            ]
+        );
+        // a colon inside an index is a slice
+        assert_eq!(
+            tokens("v[1:20]"),
+            vec![
+                Token::Identifier("v".to_string()),
+                Token::OpenBracket,
+                Token::Number(1.0),
+                Token::Colon,
+                Token::Number(20.0),
+                Token::CloseBracket
+            ]
+        );
+        assert_eq!(
+            tokens("[1:20]"),
+            vec![
+                Token::OpenBracket,
+                Token::Number(1.0),
+                Token::Colon,
+                Token::Number(20.0),
+                Token::CloseBracket
+            ]
+        );
+        assert_eq!(
+            tokens("[2026-10-18 14:30]"),
+            vec![
+                Token::OpenBracket,
+                Token::Date(20744),
+                Token::Time(52200.0),
+                Token::CloseBracket
+            ]
+        );
+        for invalid in ["2026-13-01", "2026-02-29", "24:00", "12:60"] {
+            assert!(super::tokenize(invalid.to_string()).is_err(), "{invalid}");
+        }
+    }
+
     #[test]
     fn disallowed_strings() {
         let testcases = [
@@ -185,4 +504,21 @@ mod tests {
             assert!(res.is_err(), "{:?}", res.unwrap());
         }
     }
+
+    #[test]
+    fn incomplete_strings() {
+        let testcases = [
+            ("1 +", "+"),
+            ("2 * 3 /", "/"),
+            ("max(", "("),
+            ("min(1,", ","),
+            ("let a =", "="),
+        ];
+        for (case, last) in testcases.iter() {
+            assert_eq!(
+                super::tokenize(case.to_string()),
+                Err(format!("Formula cannot end with '{}'", last))
+            );
+        }
+    }
 }
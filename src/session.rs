@@ -0,0 +1,550 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use crate::{
+    ast::{ASTParser, Expression},
+    commands::{is_command, run_command},
+    constants,
+    currency::{self, Rates},
+    dates::DateTime,
+    evaluate::evaluate,
+    functions,
+    interval::Interval,
+    random::Rng,
+    scanner::tokenize,
+    settings::Settings,
+    token::Token,
+    units::{Dimension, Quantity, Unit, UnitSystem, Units},
+    value::Value,
+};
+
+/// What running a line produced
+#[derive(Debug, PartialEq)]
+pub enum Outcome {
+    /// a calculated value and its number in the result history
+    Value(usize, Value),
+    /// a variable defined with `let`
+    Assignment(String, Value),
+    /// a unit defined with `unit`, and its value in SI units
+    UnitDefinition(String, Value),
+}
+
+impl Outcome {
+    pub fn format(&self, settings: &Settings) -> String {
+        match self {
+            Outcome::Value(n, v) => format!("${n} => {}", settings.format_value(v)),
+            Outcome::Assignment(name, v) => format!("{name} = {}", settings.format_value(v)),
+            Outcome::UnitDefinition(name, v) => {
+                format!("unit {name} = {}", settings.format_value(v))
+            }
+        }
+    }
+
+    pub fn value(&self) -> &Value {
+        match self {
+            Outcome::Value(_, v) | Outcome::Assignment(_, v) | Outcome::UnitDefinition(_, v) => v,
+        }
+    }
+}
+
+/// The state of a calculator session: user variables, the results of
+/// previous calculations, which are available as `ans` (the last one) and
+/// `$1`, `$2`, ... or `_1`, `_2`, ..., the units defined by the user, the
+/// settings and the random number generator
+#[derive(Debug, Default)]
+pub struct Session {
+    variables: HashMap<String, Value>,
+    results: Vec<Value>,
+    units: Units,
+    settings: Settings,
+    /// created on first use, from the seed setting if there is one
+    rng: Option<Rng>,
+    /// the number of sources of uncertainty created with `±`
+    sources: u64,
+}
+
+impl Session {
+    pub fn new() -> Session {
+        Default::default()
+    }
+
+    pub fn variables(&self) -> &HashMap<String, Value> {
+        &self.variables
+    }
+
+    pub fn results(&self) -> &[Value] {
+        &self.results
+    }
+
+    pub fn units(&self) -> &Units {
+        &self.units
+    }
+
+    pub fn units_mut(&mut self) -> &mut Units {
+        &mut self.units
+    }
+
+    pub fn settings(&self) -> &Settings {
+        &self.settings
+    }
+
+    pub fn settings_mut(&mut self) -> &mut Settings {
+        &mut self.settings
+    }
+
+    pub fn rng(&mut self) -> &mut Rng {
+        let seed = self.settings.seed;
+        self.rng
+            .get_or_insert_with(|| seed.map_or_else(Rng::from_time, Rng::new))
+    }
+
+    /// Loads the exchange rates of the rates setting, again if the file
+    /// changed since it was last read
+    pub fn refresh_rates(&mut self) -> Result<(), String> {
+        let Some(path) = &self.settings.rates else {
+            self.units.set_rates(None);
+            return Ok(());
+        };
+        let path = currency::expand_home(path);
+        if let Some(rates) = self.units.rates() {
+            let modified = fs::metadata(&path).and_then(|m| m.modified()).ok();
+            if rates.path == path && modified == Some(rates.modified) {
+                return Ok(());
+            }
+        }
+        let rates = Rates::load(&path);
+        self.units.set_rates(rates.as_ref().ok().cloned());
+        rates.map(|_| ())
+    }
+
+    /// Whether a name in the tokens can only be a currency, so that the
+    /// exchange rates are needed. The names a line defines are not.
+    fn may_use_currency(&self, tokens: &[Token]) -> bool {
+        let mut defined = false;
+        tokens.iter().any(|token| {
+            let after_definition =
+                std::mem::replace(&mut defined, matches!(token, Token::Let | Token::Unit));
+            match token {
+                Token::Identifier(name) => {
+                    !after_definition
+                        && !Self::is_reserved(name)
+                        && self.get(name).is_err()
+                        && constants::lookup(name).is_none()
+                        && functions::lookup(name).is_none()
+                        && self.units.lookup_physical(name).is_none()
+                }
+                _ => false,
+            }
+        })
+    }
+
+    /// When the rates of the currencies in a value were last updated
+    pub fn rates_note(&self, value: &Value) -> Option<String> {
+        let rates = self.units.rates()?;
+        fn has_currency(value: &Value, base: &str) -> bool {
+            match value {
+                Value::Number(_) | Value::Date(_) | Value::Uncertain(_) | Value::Interval(_) => {
+                    false
+                }
+                Value::Quantity(q) => q.unit.dimension.contains(base),
+                Value::List(items) => items.iter().any(|i| has_currency(i, base)),
+            }
+        }
+        has_currency(value, &rates.base).then(|| format!("(rates of {})", rates.timestamp()))
+    }
+
+    /// Identifies a new independent source of uncertainty
+    pub fn new_source(&mut self) -> u64 {
+        self.sources += 1;
+        self.sources
+    }
+
+    /// Starts the random numbers over from the seed setting
+    pub fn reseed(&mut self) {
+        self.rng = None;
+    }
+
+    /// Looks up a variable, a constant, a reference to a previous result or
+    /// the current date
+    pub fn get(&self, name: &str) -> Result<Value, String> {
+        if let Some(constant) = constants::lookup(name) {
+            if self.settings.intervals {
+                return Ok(Value::Interval(Interval::around(constant.value)));
+            }
+            return Ok(Value::Number(constant.value));
+        }
+        match name {
+            "now" => return Ok(Value::Date(DateTime::now(self.settings.timezone))),
+            "today" => return Ok(Value::Date(DateTime::today(self.settings.timezone))),
+            _ => {}
+        }
+        if name == "ans" {
+            return self
+                .results
+                .last()
+                .cloned()
+                .ok_or("ans is not defined before the first result".to_string());
+        }
+        if let Some(n) = Self::result_number(name) {
+            return match n.checked_sub(1).and_then(|i| self.results.get(i)) {
+                Some(v) => Ok(v.clone()),
+                None => Err(format!("{name} does not refer to a previous result")),
+            };
+        }
+        self.variables
+            .get(name)
+            .cloned()
+            .ok_or(format!("{name} is not defined"))
+    }
+
+    pub fn set(&mut self, name: &str, value: Value) -> Result<(), String> {
+        if Self::is_reserved(name) {
+            return Err(format!("{name} is reserved for previous results"));
+        }
+        if name == "now" || name == "today" {
+            return Err(format!("{name} is reserved for the current date"));
+        }
+        if constants::lookup(name).is_some() {
+            return Err(format!("{name} is a constant and cannot be redefined"));
+        }
+        if self.units.lookup(name).is_some() {
+            return Err(format!("{name} is already a unit"));
+        }
+        self.variables.insert(name.to_string(), value);
+        Ok(())
+    }
+
+    /// Deletes a variable, returning its value
+    pub fn remove(&mut self, name: &str) -> Result<Value, String> {
+        self.variables
+            .remove(name)
+            .ok_or(format!("{name} is not defined"))
+    }
+
+    /// Deletes all variables and previous results
+    pub fn clear(&mut self) {
+        self.variables.clear();
+        self.results.clear();
+    }
+
+    /// Adds a value to the result history and returns its number
+    pub fn record(&mut self, value: Value) -> usize {
+        self.results.push(value);
+        self.results.len()
+    }
+
+    /// Whether `name` is maintained by the session rather than the user
+    pub fn is_reserved(name: &str) -> bool {
+        name == "ans" || Self::result_number(name).is_some()
+    }
+
+    /// Tokenizes, parses and evaluates a line. Calculated values are added
+    /// to the result history, assignments are not.
+    pub fn run(&mut self, line: &str) -> Result<Outcome, String> {
+        let tokens = tokenize(line.to_string())?;
+        if self.may_use_currency(&tokens) {
+            self.refresh_rates()?;
+        }
+        let variables = self.variables.keys().cloned().collect();
+        let ast = ASTParser::create_ast(tokens, &self.units, variables)?;
+        let value = evaluate(&ast, self)?;
+        let shown = Self::in_system(&value, self.settings.units);
+        match *ast {
+            Expression::Assignment(id, _) => Ok(Outcome::Assignment(id.get_identifier()?, shown)),
+            Expression::UnitDefinition(name, _) => Ok(Outcome::UnitDefinition(name, value)),
+            // an explicit conversion wins over the unit system
+            Expression::Convert(_, _) => Ok(Outcome::Value(self.record(value.clone()), value)),
+            _ => Ok(Outcome::Value(self.record(shown.clone()), shown)),
+        }
+    }
+
+    /// Converts every quantity of a value to the unit system
+    fn in_system(value: &Value, system: UnitSystem) -> Value {
+        match value {
+            Value::Number(_) | Value::Date(_) | Value::Uncertain(_) | Value::Interval(_) => {
+                value.clone()
+            }
+            Value::Quantity(q) => Value::Quantity(q.in_system(system)),
+            Value::List(items) => {
+                Value::List(items.iter().map(|i| Self::in_system(i, system)).collect())
+            }
+        }
+    }
+
+    /// Runs every line of a script, stopping at the first error. Lines can
+    /// be formulas or commands, blank lines and lines starting with `#` are
+    /// skipped.
+    pub fn run_script(&mut self, script: &str) -> Result<(), String> {
+        for (i, line) in script.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let result = if is_command(line) {
+                run_command(line, self).map(|_| ())
+            } else {
+                self.run(line).map(|_| ())
+            };
+            result.map_err(|e| format!("Line {}: {}", i + 1, e))?;
+        }
+        Ok(())
+    }
+
+    /// Serializes the session as a script that recreates it when run: a
+    /// `:set` for every changed setting, a `unit` for every unit defined,
+    /// the previous results as plain values and a `let` for every variable
+    pub fn to_script(&self) -> String {
+        let mut script = String::from("# calculator-rs session\n");
+        // numbers would be read back as intervals once they are on
+        let (intervals, changes): (Vec<_>, Vec<_>) = self
+            .settings
+            .changes()
+            .into_iter()
+            .partition(|(key, _)| *key == "intervals");
+        for (key, value) in changes {
+            script += &format!(":set {key} {value}\n");
+        }
+        for unit in self.units.custom() {
+            if unit.dimension == Dimension::new(&[(&unit.name, 1)]) {
+                script += &format!("unit {}\n", unit.name);
+            } else {
+                let value = Value::Quantity(Quantity {
+                    value: unit.factor,
+                    unit: Unit::si(unit.dimension.clone()),
+                });
+                script += &format!("unit {} = {}\n", unit.name, Self::literal(&value));
+            }
+        }
+        for value in self.results.iter() {
+            script += &Self::literal(value);
+            script += "\n";
+        }
+        let mut variables: Vec<(&String, &Value)> = self.variables.iter().collect();
+        variables.sort_by(|a, b| a.0.cmp(b.0));
+        for (name, value) in variables {
+            script += &format!("let {} = {}\n", name, Self::literal(value));
+        }
+        for (key, value) in intervals {
+            script += &format!(":set {key} {value}\n");
+        }
+        script
+    }
+
+    /// Recreates a session from a script written by `to_script`
+    pub fn from_script(script: &str) -> Result<Session, String> {
+        let mut session = Session::new();
+        session.run_script(script)?;
+        Ok(session)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        fs::write(path, self.to_script())
+            .map_err(|e| format!("Unable to save to {}: {}", path.display(), e))
+    }
+
+    pub fn load(path: &Path) -> Result<Session, String> {
+        let script = fs::read_to_string(path)
+            .map_err(|e| format!("Unable to load {}: {}", path.display(), e))?;
+        Self::from_script(&script)
+    }
+
+    /// A formula evaluating to exactly `value`
+    fn literal(value: &Value) -> String {
+        let value = match value {
+            Value::Number(n) => *n,
+            Value::Quantity(q) if q.unit.percent => {
+                return format!("({})% as %", Self::literal(&Value::Number(q.value)));
+            }
+            Value::Quantity(q) => {
+                let value = Self::literal(&Value::Number(q.value));
+                // 1/0 km would divide by a quantity
+                if q.value.is_finite() {
+                    return format!("{} {}", value, q.unit.name);
+                }
+                return format!("({}) {}", value, q.unit.name);
+            }
+            Value::List(items) => {
+                let items: Vec<String> = items.iter().map(Self::literal).collect();
+                return format!("[{}]", items.join(", "));
+            }
+            Value::Date(date) => return date.iso(),
+            // the uncertainty is no longer correlated with other values
+            Value::Uncertain(u) => {
+                let value = Self::literal(&Value::Number(u.value));
+                let sigma = Self::literal(&Value::Number(u.uncertainty()));
+                return format!("{value} ± {sigma}");
+            }
+            Value::Interval(i) => {
+                let lo = Self::literal(&Value::Number(i.lo));
+                let hi = Self::literal(&Value::Number(i.hi));
+                return format!("[{lo} .. {hi}]");
+            }
+        };
+        if value.is_nan() {
+            "0/0".to_string()
+        } else if value.is_infinite() {
+            format!("{}1/0", if value < 0.0 { "-" } else { "" })
+        } else {
+            // Display prints the shortest representation that parses back
+            // to the same value
+            value.to_string()
+        }
+    }
+
+    /// The number in `$n` or `_n`
+    fn result_number(name: &str) -> Option<usize> {
+        let digits = name.strip_prefix('$').or(name.strip_prefix('_'))?;
+        if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        digits.parse().ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Outcome, Session};
+    use crate::commands::run_command;
+    use crate::value::Value;
+    use std::fs;
+
+    #[test]
+    fn previous_results() {
+        let mut session = Session::new();
+        assert!(session.run("ans").is_err());
+        assert_eq!(
+            session.run("1 + 2"),
+            Ok(Outcome::Value(1, Value::Number(3.0)))
+        );
+        assert_eq!(
+            session.run("ans * 2"),
+            Ok(Outcome::Value(2, Value::Number(6.0)))
+        );
+        assert_eq!(
+            session.run("let a = $1 + _2"),
+            Ok(Outcome::Assignment("a".to_string(), Value::Number(9.0)))
+        );
+        assert_eq!(
+            session.run("ans"),
+            Ok(Outcome::Value(3, Value::Number(6.0)))
+        );
+        assert_eq!(
+            session.run("a - $3"),
+            Ok(Outcome::Value(4, Value::Number(3.0)))
+        );
+        assert!(session.run("$5").is_err());
+        assert!(session.run("$0").is_err());
+        assert!(session.run("let ans = 1").is_err());
+        assert!(session.run("let _2 = 1").is_err());
+        assert!(session.run("b").is_err());
+    }
+
+    #[test]
+    fn constants_are_read_only() {
+        let mut session = Session::new();
+        assert_eq!(
+            session.run("2 * pi"),
+            Ok(Outcome::Value(1, Value::Number(std::f64::consts::TAU)))
+        );
+        assert_eq!(
+            session.run("let pi = 3"),
+            Err("pi is a constant and cannot be redefined".to_string())
+        );
+        assert!(session.run("let k_B = 1").is_err());
+        assert!(session.remove("e").is_err());
+        assert_eq!(
+            session.run("c / 1000"),
+            Ok(Outcome::Value(2, Value::Number(299792.458)))
+        );
+    }
+
+    #[test]
+    fn script_round_trip() {
+        let mut session = Session::new();
+        session.run("1 / 3").unwrap();
+        session.run("-1 / 0").unwrap();
+        session.run("let big = pow(2, 100)").unwrap();
+        session.run("let neg = -0.1").unwrap();
+        session.run("let v = [1, [2, -1 / 0]]").unwrap();
+        session.run("let grav = 9.81 m/s^2").unwrap();
+        session.run("[(1 / 0) km, 1 kg * 1 m]").unwrap();
+        session.run("2026-10-18 14:30:15.25 UTC+2").unwrap();
+        session.run("unit wafer").unwrap();
+        session.run("unit lot = 25 wafer").unwrap();
+        session.run("let batch = 4 lot / 1 h").unwrap();
+        session.run("let share = 1 / 3 as %").unwrap();
+        session.run("let range = [1 .. 1.5]").unwrap();
+        let script = session.to_script();
+        assert_eq!(
+            script,
+            "# calculator-rs session\nunit wafer\nunit lot = 25 wafer\n0.3333333333333333\n-1/0\n[(1/0) km, 1 kg m]\n2026-10-18T14:30:15.25+02:00\nlet batch = 0.027777777777777776 wafer/s\nlet big = 1267650600228229400000000000000\nlet grav = 9.81 m/s^2\nlet neg = -0.1\nlet range = [1 .. 1.5]\nlet share = (33.33333333333333)% as %\nlet v = [1, [2, -1/0]]\n"
+        );
+        let restored = Session::from_script(&script).unwrap();
+        assert_eq!(restored.results(), session.results());
+        assert_eq!(restored.variables(), session.variables());
+
+        session.settings_mut().set("precision", "2").unwrap();
+        session.settings_mut().set("prompt", "\"$ \"").unwrap();
+        session.settings_mut().set("timezone", "UTC-05:30").unwrap();
+        session.settings_mut().set("intervals", "on").unwrap();
+        session.settings_mut().set("notation", "eng").unwrap();
+        session.settings_mut().set("decimal", ",").unwrap();
+        let restored = Session::from_script(&session.to_script()).unwrap();
+        assert_eq!(restored.settings(), session.settings());
+        assert_eq!(restored.variables(), session.variables());
+    }
+
+    #[test]
+    fn converts_currencies() {
+        let dir = std::env::temp_dir().join(format!("calculator-rs-rates-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let csv = dir.join("rates.csv");
+        fs::write(&csv, "currency,rate\nEUR,1\nUSD,1.25\n").unwrap();
+        let json = dir.join("rates.json");
+        fs::write(&json, r#"{"base": "USD", "rates": {"GBP": 0.8}}"#).unwrap();
+
+        let mut session = Session::new();
+        assert_eq!(
+            session.run("1 USD").unwrap_err(),
+            "USD is not a unit, and no exchange rates file is set for currencies (:set rates FILE)"
+        );
+        let rates = format!(":set rates {}", csv.display());
+        run_command(&rates, &mut session).unwrap();
+        let outcome = session.run("100 USD in EUR").unwrap();
+        assert_eq!(outcome.value().to_string(), "80 EUR");
+        assert!(session
+            .rates_note(outcome.value())
+            .unwrap()
+            .starts_with("(rates of 20"));
+        assert_eq!(session.rates_note(&Value::Number(1.0)), None);
+        assert!(session.run("1 EUR + 1 m").is_err());
+
+        let rates = format!(":set rates {}", json.display());
+        run_command(&rates, &mut session).unwrap();
+        let outcome = session.run("4 GBP in USD").unwrap();
+        assert_eq!(outcome.value().to_string(), "5 USD");
+        assert_eq!(session.run("1 EUR").unwrap_err(), "EUR is not a unit");
+
+        let missing = format!(":set rates {}", dir.join("missing.csv").display());
+        assert!(run_command(&missing, &mut session).is_err());
+        assert!(session.run("1 GBP").is_ok());
+
+        // a broken rates file only fails the lines that need the rates
+        fs::write(&json, "{").unwrap();
+        assert!(session.run("1 + 2").is_ok());
+        assert!(session.run("let x = 3 m").is_ok());
+        assert!(session.run("x in ft").is_ok());
+        assert!(session
+            .run("1 GBP")
+            .unwrap_err()
+            .starts_with("Invalid rates"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn script_errors_name_the_line() {
+        assert_eq!(
+            Session::from_script("let a = 1\n\nlet b = d").unwrap_err(),
+            "Line 3: d is not defined"
+        );
+    }
+}
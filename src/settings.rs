@@ -0,0 +1,428 @@
+use std::{env, path::PathBuf};
+
+use crate::{
+    dates,
+    format::{Format, Notation, Style, MAX_DIGITS},
+    units::UnitSystem,
+    value::Value,
+};
+
+/// The unit angles are measured in by the trigonometric functions
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum AngleUnit {
+    #[default]
+    Radians,
+    Degrees,
+    Gradians,
+}
+
+impl AngleUnit {
+    /// The size of a full turn in this unit
+    fn turn(self) -> f64 {
+        match self {
+            AngleUnit::Radians => std::f64::consts::TAU,
+            AngleUnit::Degrees => 360.0,
+            AngleUnit::Gradians => 400.0,
+        }
+    }
+
+    /// Converts an angle measured in `unit` to this unit
+    pub fn convert(self, angle: f64, unit: AngleUnit) -> f64 {
+        if self == unit {
+            return angle;
+        }
+        angle / unit.turn() * self.turn()
+    }
+
+    pub fn to_radians(self, angle: f64) -> f64 {
+        AngleUnit::Radians.convert(angle, self)
+    }
+
+    pub fn from_radians(self, angle: f64) -> f64 {
+        self.convert(angle, AngleUnit::Radians)
+    }
+}
+
+impl std::fmt::Display for AngleUnit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            AngleUnit::Radians => "rad",
+            AngleUnit::Degrees => "deg",
+            AngleUnit::Gradians => "grad",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// User preferences of a session, changed with `:set` or the config file
+#[derive(Debug, Clone, PartialEq)]
+pub struct Settings {
+    pub prompt: String,
+    /// digits shown in the notation, all of them when unset
+    pub precision: Option<usize>,
+    pub notation: Notation,
+    /// whether thousands are separated
+    pub grouping: bool,
+    /// `.` or `,`
+    pub decimal: char,
+    pub angle: AngleUnit,
+    /// seed of the random numbers, a new one for every session when unset
+    pub seed: Option<u64>,
+    /// the unit system results are shown in
+    pub units: UnitSystem,
+    /// the file with the exchange rates of currencies
+    pub rates: Option<String>,
+    /// offset from UTC in minutes of the dates entered and shown
+    pub timezone: i32,
+    /// whether numbers are intervals that bound their rounding errors
+    pub intervals: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            prompt: "> ".to_string(),
+            precision: None,
+            notation: Notation::Auto,
+            grouping: false,
+            decimal: '.',
+            angle: AngleUnit::Radians,
+            seed: None,
+            units: UnitSystem::AsEntered,
+            rates: None,
+            timezone: 0,
+            intervals: false,
+        }
+    }
+}
+
+impl Settings {
+    /// The settings that can be changed and what their values mean
+    pub const KEYS: &'static [(&'static str, &'static str)] = &[
+        (
+            "prompt",
+            "text shown before the cursor, quote it to keep spaces",
+        ),
+        (
+            "precision",
+            "decimal places shown, at most with auto, significant digits with sig, or 'default'",
+        ),
+        (
+            "notation",
+            "how numbers are shown: auto, fix, sig, sci, eng or si",
+        ),
+        ("grouping", "on to separate thousands, or off"),
+        ("decimal", "decimal separator: . or ,"),
+        ("angle", "unit of angles in trigonometry: rad, deg or grad"),
+        (
+            "seed",
+            "seed of the random numbers, a whole number or 'random'",
+        ),
+        (
+            "units",
+            "units of results: none (as calculated), si, imperial or cgs",
+        ),
+        ("rates", "file of exchange rates, CSV or JSON, or 'none'"),
+        ("timezone", "time zone of dates, e.g. UTC or UTC+2"),
+        (
+            "intervals",
+            "on to bound every result with outward-rounded intervals, or off",
+        ),
+    ];
+
+    /// Changes a setting from its textual value
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        let value = value.trim();
+        match key {
+            "prompt" => {
+                self.prompt = unquote(value).to_string();
+            }
+            "precision" => {
+                self.precision = match value {
+                    "default" => None,
+                    _ => match value.parse::<usize>() {
+                        Ok(digits) if digits <= MAX_DIGITS => Some(digits),
+                        Ok(_) => {
+                            return Err(format!(
+                                "precision must be at most {MAX_DIGITS} digits, not {value}"
+                            ))
+                        }
+                        Err(_) => {
+                            return Err(format!(
+                                "precision must be a number of digits, not {value}"
+                            ))
+                        }
+                    },
+                }
+            }
+            "notation" => {
+                self.notation = Notation::parse(value).ok_or(format!(
+                    "notation must be one of {}, not {value}",
+                    Notation::NAMES.join(", ")
+                ))?
+            }
+            "grouping" => {
+                self.grouping = match value {
+                    "on" => true,
+                    "off" => false,
+                    _ => return Err(format!("grouping must be on or off, not {value}")),
+                }
+            }
+            "decimal" => {
+                self.decimal = match value {
+                    "." => '.',
+                    "," => ',',
+                    _ => return Err(format!("decimal must be . or , not {value}")),
+                }
+            }
+            "angle" => {
+                self.angle = match value {
+                    "rad" => AngleUnit::Radians,
+                    "deg" => AngleUnit::Degrees,
+                    "grad" => AngleUnit::Gradians,
+                    _ => return Err(format!("angle must be rad, deg or grad, not {value}")),
+                }
+            }
+            "seed" => {
+                self.seed = match value {
+                    "random" => None,
+                    _ => Some(value.parse::<u64>().map_err(|_| {
+                        format!("seed must be a whole number or random, not {value}")
+                    })?),
+                }
+            }
+            "units" => {
+                self.units = match value {
+                    "none" => UnitSystem::AsEntered,
+                    "si" => UnitSystem::Si,
+                    "imperial" => UnitSystem::Imperial,
+                    "cgs" => UnitSystem::Cgs,
+                    _ => {
+                        return Err(format!(
+                            "units must be none, si, imperial or cgs, not {value}"
+                        ))
+                    }
+                }
+            }
+            "rates" => {
+                self.rates = match unquote(value) {
+                    "none" => None,
+                    path => Some(path.to_string()),
+                }
+            }
+            "timezone" => {
+                self.timezone = dates::parse_zone(value).ok_or(format!(
+                    "timezone must be UTC or an offset like UTC+2 or UTC-05:30, not {value}"
+                ))?
+            }
+            "intervals" => {
+                self.intervals = match value {
+                    "on" => true,
+                    "off" => false,
+                    _ => return Err(format!("intervals must be on or off, not {value}")),
+                }
+            }
+            _ => return Err(format!("{key} is not a setting")),
+        }
+        Ok(())
+    }
+
+    /// The textual value of a setting, as accepted by `set`
+    pub fn get(&self, key: &str) -> Option<String> {
+        match key {
+            "prompt" => Some(format!("\"{}\"", self.prompt)),
+            "precision" => Some(
+                self.precision
+                    .map_or("default".to_string(), |p| p.to_string()),
+            ),
+            "notation" => Some(self.notation.to_string()),
+            "grouping" => Some(if self.grouping { "on" } else { "off" }.to_string()),
+            "decimal" => Some(self.decimal.to_string()),
+            "angle" => Some(self.angle.to_string()),
+            "seed" => Some(self.seed.map_or("random".to_string(), |s| s.to_string())),
+            "units" => Some(self.units.to_string()),
+            "rates" => Some(
+                self.rates
+                    .as_ref()
+                    .map_or("none".to_string(), |path| format!("\"{path}\"")),
+            ),
+            "timezone" => Some(dates::format_zone(self.timezone)),
+            "intervals" => Some(if self.intervals { "on" } else { "off" }.to_string()),
+            _ => None,
+        }
+    }
+
+    /// The settings that differ from the defaults, as `:set` arguments
+    pub fn changes(&self) -> Vec<(&'static str, String)> {
+        let default = Settings::default();
+        Self::KEYS
+            .iter()
+            .map(|(key, _)| (*key, self.get(key)))
+            .filter(|(key, value)| *value != default.get(key))
+            .filter_map(|(key, value)| Some((key, value?)))
+            .collect()
+    }
+
+    /// Applies a config file of `key = value` lines. Blank lines and lines
+    /// starting with `#` are skipped. Returns an error for every line that
+    /// could not be applied, the others still take effect.
+    pub fn apply_config(&mut self, config: &str) -> Vec<String> {
+        let mut errors = Vec::new();
+        for (i, line) in config.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let result = match line.split_once('=') {
+                Some((key, value)) => self.set(key.trim(), value),
+                None => Err("expected key = value".to_string()),
+            };
+            if let Err(e) = result {
+                errors.push(format!("Line {}: {}", i + 1, e));
+            }
+        }
+        errors
+    }
+
+    /// `$XDG_CONFIG_HOME/calculator-rs`, falling back to
+    /// `~/.config/calculator-rs`. Holds the `config` file and the `init`
+    /// script run at startup.
+    pub fn config_dir() -> Option<PathBuf> {
+        let config_home = match env::var_os("XDG_CONFIG_HOME") {
+            Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+            _ => PathBuf::from(env::var_os("HOME")?).join(".config"),
+        };
+        Some(config_home.join("calculator-rs"))
+    }
+
+    /// These settings with the notation of a single result
+    pub fn with_style(&self, style: Option<Style>) -> Settings {
+        let mut settings = self.clone();
+        if let Some(style) = style {
+            settings.notation = style.notation;
+            settings.precision = style.digits;
+        }
+        settings
+    }
+
+    pub fn format(&self, value: f64) -> String {
+        Format {
+            notation: self.notation,
+            digits: self.precision,
+            grouping: self.grouping,
+            decimal: self.decimal,
+        }
+        .number(value)
+    }
+
+    /// Formats every number of a value with `format`
+    pub fn format_value(&self, value: &Value) -> String {
+        match value {
+            Value::Number(n) => self.format(*n),
+            Value::Quantity(q) if q.unit.percent => format!("{}%", self.format(q.value)),
+            Value::Quantity(q) => format!("{} {}", self.format(q.value), q.unit.name),
+            // the time zone is left out when it is the one of the session
+            Value::Date(d) => d.format(d.offset != self.timezone),
+            Value::Uncertain(u) => u.format(),
+            // bounds are rounded outwards so they still hold, other
+            // notations show all their digits
+            Value::Interval(i) if !i.is_point() && self.notation != Notation::Auto => {
+                let all = self.with_style(Some(Style {
+                    notation: self.notation,
+                    digits: None,
+                }));
+                format!("[{} .. {}]", all.format(i.lo), all.format(i.hi))
+            }
+            Value::Interval(i) => match self.precision {
+                Some(precision) if !i.is_point() => {
+                    let scale = 10f64.powi(precision as i32);
+                    let lo = Value::Number((i.lo * scale).floor() / scale);
+                    let hi = Value::Number((i.hi * scale).ceil() / scale);
+                    format!("[{} .. {}]", self.format_value(&lo), self.format_value(&hi))
+                }
+                _ if i.is_point() => self.format(i.lo),
+                _ => format!("[{} .. {}]", self.format(i.lo), self.format(i.hi)),
+            },
+            Value::List(items) => {
+                let items: Vec<String> = items.iter().map(|i| self.format_value(i)).collect();
+                // `[1,5, 2,5]` would be ambiguous with a decimal comma
+                let separator = if self.decimal == ',' { "; " } else { ", " };
+                format!("[{}]", items.join(separator))
+            }
+        }
+    }
+}
+
+fn unquote(value: &str) -> &str {
+    value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .unwrap_or(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Settings;
+    use crate::{
+        format::{Notation, Style},
+        value::Value,
+    };
+
+    #[test]
+    fn applies_config() {
+        let mut settings = Settings::default();
+        let errors = settings.apply_config(
+            "# defaults\n\
+             prompt = \"calc> \"\n\
+             precision = 3\n\
+             colour = blue\n\
+             precision\n",
+        );
+        assert_eq!(settings.prompt, "calc> ");
+        assert_eq!(settings.precision, Some(3));
+        assert_eq!(
+            errors,
+            [
+                "Line 4: colour is not a setting",
+                "Line 5: expected key = value"
+            ]
+        );
+        assert!(settings.set("precision", "many").is_err());
+    }
+
+    #[test]
+    fn converts_angles() {
+        use super::AngleUnit::{Degrees, Gradians, Radians};
+        assert_eq!(Degrees.convert(100.0, Gradians), 90.0);
+        assert_eq!(Gradians.from_radians(std::f64::consts::PI), 200.0);
+        assert_eq!(Degrees.to_radians(180.0), std::f64::consts::PI);
+        assert_eq!(Radians.convert(1.2, Radians), 1.2);
+    }
+
+    #[test]
+    fn formats_with_precision() {
+        let mut settings = Settings::default();
+        assert_eq!(settings.format(1.0 / 3.0), "0.3333333333333333");
+        settings.precision = Some(3);
+        assert_eq!(settings.format(1.0 / 3.0), "0.333");
+        assert_eq!(settings.format(2.5), "2.5");
+        assert_eq!(settings.format(-0.0001), "0");
+        assert_eq!(settings.format(1.0 / 0.0), "inf");
+        settings.precision = Some(0);
+        assert_eq!(settings.format(2.5), "2");
+        settings.set("notation", "sig").unwrap();
+        settings.set("grouping", "on").unwrap();
+        settings.set("decimal", ",").unwrap();
+        let style = Some(Style {
+            notation: Notation::Fixed,
+            digits: Some(2),
+        });
+        assert_eq!(settings.with_style(style).format(12345.678), "12.345,68");
+        settings.precision = None;
+        let list = Value::List(vec![Value::Number(1.5), Value::Number(2.5)]);
+        assert_eq!(settings.format_value(&list), "[1,5; 2,5]");
+        assert!(settings.set("notation", "roman").is_err());
+        assert!(settings.set("precision", "17").is_ok());
+        assert!(settings.set("precision", "100").is_err());
+    }
+}
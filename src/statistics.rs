@@ -0,0 +1,118 @@
+//! Descriptive statistics of a sample, given either as the arguments of a
+//! function or as a single list
+
+use crate::{value::Value, vector};
+
+/// The numbers to describe: the elements of a single list argument, or the
+/// arguments themselves
+pub fn values(name: &str, args: &[Value]) -> Result<Vec<f64>, String> {
+    let values = match args {
+        [Value::List(items)] => vector::numbers(items)?,
+        _ => vector::numbers(args)?,
+    };
+    if values.is_empty() {
+        return Err(format!("{name} requires at least one value"));
+    }
+    Ok(values)
+}
+
+pub fn mean(values: &[f64]) -> f64 {
+    values.iter().sum::<f64>() / values.len() as f64
+}
+
+/// The most frequent value, the smallest one if there are several
+pub fn mode(values: &[f64]) -> f64 {
+    let mut sorted = values.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    let mut best = (sorted[0], 0);
+    for run in sorted.chunk_by(|a, b| a == b) {
+        if run.len() > best.1 {
+            best = (run[0], run.len());
+        }
+    }
+    best.0
+}
+
+/// Variance of a sample, or of a whole population
+pub fn variance(name: &str, values: &[f64], sample: bool) -> Result<f64, String> {
+    if sample && values.len() < 2 {
+        return Err(format!("{name} of a sample requires at least two values"));
+    }
+    let mean = mean(values);
+    let squares: f64 = values.iter().map(|x| (x - mean).powi(2)).sum();
+    let n = values.len() - sample as usize;
+    Ok(squares / n as f64)
+}
+
+/// The value below which `p` percent of the values lie, interpolating
+/// linearly between the closest values
+pub fn percentile(values: &[f64], p: f64) -> Result<f64, String> {
+    if !(0.0..=100.0).contains(&p) {
+        return Err(format!("percentile must be from 0 to 100, not {p}"));
+    }
+    let mut sorted = values.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    let rank = p / 100.0 * (sorted.len() - 1) as f64;
+    let (below, above) = (rank.floor() as usize, rank.ceil() as usize);
+    Ok(sorted[below] + (sorted[above] - sorted[below]) * rank.fract())
+}
+
+/// Applies the statistic `name` to the values
+pub fn describe(name: &str, values: &[f64]) -> Result<Value, String> {
+    let value = match name {
+        "mean" => mean(values),
+        "median" => percentile(values, 50.0)?,
+        "mode" => mode(values),
+        "var" => variance(name, values, true)?,
+        "pvar" => variance(name, values, false)?,
+        "stddev" => variance(name, values, true)?.sqrt(),
+        "pstddev" => variance(name, values, false)?.sqrt(),
+        "spread" => {
+            let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+            let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+            max - min
+        }
+        "quartiles" => {
+            let quartiles = [25.0, 50.0, 75.0]
+                .iter()
+                .map(|&p| percentile(values, p).map(Value::Number))
+                .collect::<Result<Vec<Value>, String>>()?;
+            return Ok(Value::List(quartiles));
+        }
+        "zscore" => {
+            let mean = mean(values);
+            let stddev = variance(name, values, true)?.sqrt();
+            if stddev == 0.0 {
+                return Err(format!(
+                    "{name} is not defined for values that are all equal"
+                ));
+            }
+            let scores = values.iter().map(|x| Value::Number((x - mean) / stddev));
+            return Ok(Value::List(scores.collect()));
+        }
+        _ => unreachable!(),
+    };
+    Ok(Value::Number(value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn describes_samples() {
+        let values = [2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0];
+        assert_eq!(mean(&values), 5.0);
+        assert_eq!(mode(&values), 4.0);
+        assert_eq!(mode(&[3.0, 1.0, 3.0, 1.0]), 1.0);
+        assert_eq!(variance("pvar", &values, false), Ok(4.0));
+        assert_eq!(variance("var", &values, true), Ok(32.0 / 7.0));
+        assert!(variance("var", &[1.0], true).is_err());
+        assert_eq!(percentile(&values, 50.0), Ok(4.5));
+        assert_eq!(percentile(&[1.0, 2.0, 3.0, 4.0], 25.0), Ok(1.75));
+        assert_eq!(percentile(&[3.0], 90.0), Ok(3.0));
+        assert!(percentile(&values, 101.0).is_err());
+        assert_eq!(describe("spread", &values), Ok(Value::Number(7.0)));
+        assert!(describe("zscore", &[3.0, 3.0, 3.0]).is_err());
+    }
+}
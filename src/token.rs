@@ -1,3 +1,5 @@
+use crate::dates;
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum Token {
     Plus,
@@ -18,14 +20,38 @@ pub enum Token {
     Tan,
     Log,
     Comma,
-    Number(f32),
+    Number(f64),
     Identifier(String),
     Let,
-    Equal
+    Equal,
+    Deg,
+    Rad,
+    Grad,
+    Bang,
+    OpenBracket,
+    CloseBracket,
+    Colon,
+    At,
+    Caret,
+    To,
+    In,
+    Unit,
+    Mod,
+    Of,
+    Off,
+    As,
+    PlusMinus,
+    DotDot,
+    /// a date as days since 1970-01-01
+    Date(i64),
+    /// a time of day as seconds since midnight
+    Time(f64),
+    /// a time zone as its offset from UTC in minutes
+    Zone(i32),
 }
 
 impl Token {
-    pub fn get_number(&self) -> Result<f32, String> {
+    pub fn get_number(&self) -> Result<f64, String> {
         match self {
             Self::Number(n) => Ok(*n),
             _ => Err(format!("{} is not a number token", self)),
@@ -64,7 +90,42 @@ impl std::fmt::Display for Token {
             Token::Ceil => String::from("ceil"),
             Token::Identifier(s) => s.clone(),
             Token::Let => String::from("let"),
-            Token::Equal => String::from("=")
+            Token::Equal => String::from("="),
+            Token::Deg => String::from("deg"),
+            Token::Rad => String::from("rad"),
+            Token::Grad => String::from("grad"),
+            Token::Bang => String::from("!"),
+            Token::OpenBracket => String::from("["),
+            Token::CloseBracket => String::from("]"),
+            Token::Colon => String::from(":"),
+            Token::At => String::from("@"),
+            Token::Caret => String::from("^"),
+            Token::To => String::from("to"),
+            Token::In => String::from("in"),
+            Token::Unit => String::from("unit"),
+            Token::Mod => String::from("mod"),
+            Token::Of => String::from("of"),
+            Token::Off => String::from("off"),
+            Token::As => String::from("as"),
+            Token::PlusMinus => String::from("±"),
+            Token::DotDot => String::from(".."),
+            Token::Date(days) => {
+                let (year, month, day) = dates::civil_from_days(*days);
+                format!("{year:04}-{month:02}-{day:02}")
+            }
+            Token::Time(seconds) => {
+                let minutes = (seconds / 60.0).floor();
+                let mut time = format!("{:02}:{:02}", minutes as i64 / 60, minutes as i64 % 60);
+                if seconds % 60.0 != 0.0 {
+                    time += &format!(
+                        ":{}{}",
+                        if seconds % 60.0 < 10.0 { "0" } else { "" },
+                        seconds % 60.0
+                    );
+                }
+                time
+            }
+            Token::Zone(offset) => dates::format_zone(*offset),
         };
         write!(f, "{}", s)
     }
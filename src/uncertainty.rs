@@ -0,0 +1,113 @@
+//! Values with a standard uncertainty, e.g. `9.81 ± 0.02`. Every `±`
+//! introduces an independent source of uncertainty and a value keeps how
+//! much it depends on each source, so that the uncertainty of values
+//! calculated from the same variable is correlated: `g - g` is exactly 0.
+//! Uncertainties are propagated linearly, with the first derivatives of
+//! the operations.
+
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Uncertain {
+    pub value: f64,
+    /// the change of the value with each source of uncertainty, scaled by
+    /// the standard uncertainty of the source
+    pub terms: BTreeMap<u64, f64>,
+}
+
+impl Uncertain {
+    /// A value with the standard uncertainty `sigma` from a new `source`
+    pub fn new(value: f64, sigma: f64, source: u64) -> Uncertain {
+        Uncertain {
+            value,
+            terms: BTreeMap::from([(source, sigma)]),
+        }
+    }
+
+    /// A value without uncertainty
+    pub fn exact(value: f64) -> Uncertain {
+        Uncertain {
+            value,
+            terms: BTreeMap::new(),
+        }
+    }
+
+    pub fn uncertainty(&self) -> f64 {
+        self.terms.values().fold(0.0, |sum, t| sum + t * t).sqrt()
+    }
+
+    /// The result `value` of an operation on `args`, each given with the
+    /// partial derivative of the operation with respect to it
+    pub fn combine(value: f64, args: &[(&Uncertain, f64)]) -> Uncertain {
+        let mut terms = BTreeMap::new();
+        for (arg, derivative) in args {
+            for (source, term) in arg.terms.iter() {
+                *terms.entry(*source).or_insert(0.0) += derivative * term;
+            }
+        }
+        Uncertain { value, terms }
+    }
+
+    /// Rounds the uncertainty to two significant digits and the value to
+    /// the same decimal place, e.g. `9.810 ± 0.020`. Very large or small
+    /// values share a power of ten, `(6.674 ± 0.015)e-11`.
+    pub fn format(&self) -> String {
+        let sigma = self.uncertainty();
+        if sigma == 0.0 || !sigma.is_finite() || !self.value.is_finite() {
+            return format!("{} ± {}", self.value, sigma);
+        }
+        let magnitude = if self.value == 0.0 {
+            sigma
+        } else {
+            self.value.abs()
+        };
+        let exponent = magnitude.log10().floor() as i32;
+        if !(-4..6).contains(&exponent) {
+            let scale = 10f64.powi(exponent);
+            let scaled = Uncertain::combine(self.value / scale, &[(self, 1.0 / scale)]);
+            return format!("({})e{}", scaled.format(), exponent);
+        }
+        let places = 1 - sigma.log10().floor() as i32;
+        if places >= 0 {
+            let places = places as usize;
+            return format!("{:.*} ± {:.*}", places, self.value, places, sigma);
+        }
+        let scale = 10f64.powi(-places);
+        let round = |x: f64| (x / scale).round() * scale;
+        format!("{} ± {}", round(self.value), round(sigma))
+    }
+}
+
+impl std::fmt::Display for Uncertain {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.format())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn propagates_correlated_uncertainty() {
+        let g = Uncertain::new(9.81, 0.02, 1);
+        let h = Uncertain::new(2.0, 0.1, 2);
+        let difference = Uncertain::combine(0.0, &[(&g, 1.0), (&g, -1.0)]);
+        assert_eq!(difference.uncertainty(), 0.0);
+        let product = Uncertain::combine(g.value * h.value, &[(&g, h.value), (&h, g.value)]);
+        assert!(
+            (product.uncertainty() - (0.04f64.powi(2) + 0.981f64.powi(2)).sqrt()).abs() < 1e-12
+        );
+        assert_eq!(g.format(), "9.810 ± 0.020");
+        assert_eq!(Uncertain::new(4567.0, 123.0, 3).format(), "4570 ± 120");
+        assert_eq!(
+            Uncertain::new(1.23456, 0.0015, 4).format(),
+            "1.2346 ± 0.0015"
+        );
+        assert_eq!(Uncertain::exact(2.5).format(), "2.5 ± 0");
+        assert_eq!(
+            Uncertain::new(6.6743e-11, 1.5e-15, 5).format(),
+            "(6.67430 ± 0.00015)e-11"
+        );
+    }
+}
@@ -0,0 +1,672 @@
+//! Physical units. A quantity keeps the number in the unit it was given in,
+//! together with the factor of that unit to SI base units and its
+//! dimension, the exponents of the base units.
+
+use std::collections::BTreeMap;
+
+use crate::currency::Rates;
+
+/// The SI base units in the order they are shown in
+const BASE_UNITS: [&str; 7] = ["kg", "m", "s", "A", "K", "mol", "cd"];
+
+/// Exponents of base units, e.g. m s^-2 for an acceleration
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Dimension(BTreeMap<String, i32>);
+
+impl Dimension {
+    pub fn new(exponents: &[(&str, i32)]) -> Dimension {
+        let mut dimension = BTreeMap::new();
+        for (base, exponent) in exponents {
+            *dimension.entry(base.to_string()).or_insert(0) += exponent;
+        }
+        dimension.retain(|_, e| *e != 0);
+        Dimension(dimension)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Whether the base unit is part of the dimension
+    pub fn contains(&self, base: &str) -> bool {
+        self.0.contains_key(base)
+    }
+
+    pub fn mul(&self, other: &Dimension) -> Result<Dimension, String> {
+        let mut exponents = self.0.clone();
+        for (base, exponent) in &other.0 {
+            let e = exponents.entry(base.clone()).or_insert(0);
+            *e = e.checked_add(*exponent).ok_or_else(|| too_large(base))?;
+        }
+        exponents.retain(|_, e| *e != 0);
+        Ok(Dimension(exponents))
+    }
+
+    pub fn powi(&self, n: i32) -> Result<Dimension, String> {
+        let mut exponents = self.0.clone();
+        for (base, e) in exponents.iter_mut() {
+            *e = e.checked_mul(n).ok_or_else(|| too_large(base))?;
+        }
+        exponents.retain(|_, e| *e != 0);
+        Ok(Dimension(exponents))
+    }
+
+    /// The dimension raised to a fractional power, if all exponents stay
+    /// whole numbers
+    pub fn powf(&self, x: f64) -> Result<Dimension, String> {
+        let mut exponents = BTreeMap::new();
+        for (base, e) in &self.0 {
+            let scaled = *e as f64 * x;
+            if scaled.fract() != 0.0 {
+                return Err("the exponents of its unit would not be whole numbers".to_string());
+            }
+            if scaled.abs() > i32::MAX as f64 {
+                return Err(too_large(base));
+            }
+            if scaled != 0.0 {
+                exponents.insert(base.clone(), scaled as i32);
+            }
+        }
+        Ok(Dimension(exponents))
+    }
+
+    /// The base units with their exponents, SI base units first
+    fn exponents(&self) -> Vec<(&str, i32)> {
+        let position = |base: &str| BASE_UNITS.iter().position(|b| *b == base);
+        let mut exponents: Vec<(&str, i32)> =
+            self.0.iter().map(|(b, e)| (b.as_str(), *e)).collect();
+        exponents.sort_by_key(|(base, _)| position(base).unwrap_or(BASE_UNITS.len()));
+        exponents
+    }
+}
+
+fn too_large(base: &str) -> String {
+    format!("the exponent of {base} would be too large")
+}
+
+/// A unit, possibly composed like `km/h`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Unit {
+    pub name: String,
+    /// value of one of this unit in SI base units
+    pub factor: f64,
+    pub dimension: Dimension,
+    /// a percentage, written right after the number as in `5%`
+    pub percent: bool,
+}
+
+impl Unit {
+    /// The coherent SI unit of a dimension, named after a derived unit if
+    /// one matches, e.g. N for kg m s^-2
+    pub fn si(dimension: Dimension) -> Unit {
+        let derived = UNITS
+            .iter()
+            .filter(|u| u.derived)
+            .find(|u| Dimension::new(u.dimension) == dimension);
+        let name = match derived {
+            Some(u) => u.name.to_string(),
+            None => compose(&dimension.exponents()),
+        };
+        Unit {
+            name,
+            factor: 1.0,
+            dimension,
+            percent: false,
+        }
+    }
+
+    /// A hundredth, the unit numbers are shown in by `as %`
+    pub fn percent() -> Unit {
+        Unit {
+            name: "%".to_string(),
+            factor: 0.01,
+            dimension: Dimension::default(),
+            percent: true,
+        }
+    }
+}
+
+/// Writes units with exponents like `kg m^2/s^2`, or `s^-1` when there is
+/// nothing to divide
+pub fn compose(factors: &[(&str, i32)]) -> String {
+    let power = |name: &str, e: i32| {
+        if e == 1 {
+            name.to_string()
+        } else {
+            format!("{name}^{e}")
+        }
+    };
+    let numerator: Vec<String> = factors
+        .iter()
+        .filter(|(_, e)| *e > 0)
+        .map(|(n, e)| power(n, *e))
+        .collect();
+    if numerator.is_empty() {
+        let factors: Vec<String> = factors.iter().map(|(n, e)| power(n, *e)).collect();
+        return factors.join(" ");
+    }
+    let mut name = numerator.join(" ");
+    for (n, e) in factors.iter().filter(|(_, e)| *e < 0) {
+        name += "/";
+        name += &power(n, -e);
+    }
+    name
+}
+
+/// Splits a unit written like `kg m^2/s^2`, as `compose` writes it, into
+/// names with exponents
+pub fn split(name: &str) -> Vec<(String, i32)> {
+    let mut factors = Vec::new();
+    for (i, part) in name.split('/').enumerate() {
+        let sign = if i == 0 { 1 } else { -1 };
+        for factor in part.split_whitespace() {
+            let (name, exponent) = match factor.split_once('^') {
+                Some((name, e)) => (name, e.parse().unwrap_or(1)),
+                None => (factor, 1),
+            };
+            factors.push((name.to_string(), sign * exponent));
+        }
+    }
+    factors
+}
+
+/// A number with a unit
+#[derive(Debug, Clone, PartialEq)]
+pub struct Quantity {
+    pub value: f64,
+    pub unit: Unit,
+}
+
+impl Quantity {
+    pub fn si_value(&self) -> f64 {
+        self.value * self.unit.factor
+    }
+
+    /// The same quantity in the unit of `system` that shows it with the
+    /// smallest number that is at least 1
+    pub fn in_system(&self, system: UnitSystem) -> Quantity {
+        if system == UnitSystem::AsEntered || self.unit.dimension.is_empty() {
+            return self.clone();
+        }
+        let options_in = |system: UnitSystem| {
+            let mut options: Vec<Unit> = system
+                .candidates()
+                .iter()
+                .filter_map(|name| Units::default().resolve(&split(name)).ok())
+                .filter(|u| u.dimension == self.unit.dimension)
+                .collect();
+            options.sort_by(|a, b| a.factor.total_cmp(&b.factor));
+            options
+        };
+        let mut options = options_in(system);
+        if options.is_empty() {
+            options = options_in(UnitSystem::Si);
+        }
+        let si = self.si_value().abs();
+        let si_bases = self
+            .unit
+            .dimension
+            .exponents()
+            .iter()
+            .all(|(b, _)| BASE_UNITS.contains(b));
+        let unit = if options.is_empty() && !si_bases {
+            // units defined by the user have no better name
+            return self.clone();
+        } else if options.is_empty() {
+            Unit::si(self.unit.dimension.clone())
+        } else if si == 0.0 || !si.is_finite() {
+            // the unit closest to the SI one
+            let distance = |u: &Unit| u.factor.ln().abs();
+            let closest = options
+                .iter()
+                .min_by(|a, b| distance(a).total_cmp(&distance(b)));
+            closest.unwrap().clone()
+        } else {
+            let largest = options.iter().rev().find(|u| si >= u.factor);
+            largest.unwrap_or(&options[0]).clone()
+        };
+        Quantity {
+            value: self.si_value() / unit.factor,
+            unit,
+        }
+    }
+
+    /// The same quantity in another unit of the same dimension
+    pub fn convert(&self, unit: &Unit) -> Result<Quantity, String> {
+        if self.unit.dimension != unit.dimension {
+            return Err(format!("Cannot convert {} to {}", self, unit.name));
+        }
+        Ok(Quantity {
+            value: self.si_value() / unit.factor,
+            unit: unit.clone(),
+        })
+    }
+}
+
+impl std::fmt::Display for Quantity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.unit.percent {
+            return write!(f, "{}%", self.value);
+        }
+        write!(f, "{} {}", self.value, self.unit.name)
+    }
+}
+
+/// A built-in unit
+#[derive(Debug, PartialEq)]
+pub struct UnitDef {
+    pub name: &'static str,
+    pub factor: f64,
+    pub dimension: &'static [(&'static str, i32)],
+    /// whether SI prefixes can be put in front, e.g. km
+    pub prefixes: bool,
+    /// whether results of this dimension are shown in this unit
+    pub derived: bool,
+    pub description: &'static str,
+}
+
+const fn unit(
+    name: &'static str,
+    factor: f64,
+    dimension: &'static [(&'static str, i32)],
+    prefixes: bool,
+    description: &'static str,
+) -> UnitDef {
+    UnitDef {
+        name,
+        factor,
+        dimension,
+        prefixes,
+        derived: false,
+        description,
+    }
+}
+
+const fn derived(
+    name: &'static str,
+    dimension: &'static [(&'static str, i32)],
+    description: &'static str,
+) -> UnitDef {
+    UnitDef {
+        name,
+        factor: 1.0,
+        dimension,
+        prefixes: true,
+        derived: true,
+        description,
+    }
+}
+
+const LENGTH: &[(&str, i32)] = &[("m", 1)];
+const MASS: &[(&str, i32)] = &[("kg", 1)];
+const TIME: &[(&str, i32)] = &[("s", 1)];
+const VOLUME: &[(&str, i32)] = &[("m", 3)];
+const SPEED: &[(&str, i32)] = &[("m", 1), ("s", -1)];
+const FORCE: &[(&str, i32)] = &[("kg", 1), ("m", 1), ("s", -2)];
+const PRESSURE: &[(&str, i32)] = &[("kg", 1), ("m", -1), ("s", -2)];
+const ENERGY: &[(&str, i32)] = &[("kg", 1), ("m", 2), ("s", -2)];
+const POWER: &[(&str, i32)] = &[("kg", 1), ("m", 2), ("s", -3)];
+
+pub const UNITS: &[UnitDef] = &[
+    unit("m", 1.0, LENGTH, true, "metre"),
+    unit("inch", 0.0254, LENGTH, false, "inch"),
+    unit("ft", 0.3048, LENGTH, false, "foot"),
+    unit("yd", 0.9144, LENGTH, false, "yard"),
+    unit("mi", 1609.344, LENGTH, false, "mile"),
+    unit("nmi", 1852.0, LENGTH, false, "nautical mile"),
+    unit("g", 1e-3, MASS, true, "gram"),
+    unit("t", 1e3, MASS, true, "tonne"),
+    unit("lb", 0.45359237, MASS, false, "pound"),
+    unit("oz", 0.028349523125, MASS, false, "ounce"),
+    unit("s", 1.0, TIME, true, "second"),
+    unit("min", 60.0, TIME, false, "minute"),
+    unit("h", 3600.0, TIME, false, "hour"),
+    unit("d", 86400.0, TIME, false, "day"),
+    unit("week", 604800.0, TIME, false, "week"),
+    unit(
+        "month",
+        2629800.0,
+        TIME,
+        false,
+        "month, 1/12 of a Julian year",
+    ),
+    unit("yr", 31557600.0, TIME, false, "Julian year, 365.25 days"),
+    unit("A", 1.0, &[("A", 1)], true, "ampere"),
+    unit("K", 1.0, &[("K", 1)], true, "kelvin"),
+    unit("mol", 1.0, &[("mol", 1)], true, "mole"),
+    unit("cd", 1.0, &[("cd", 1)], true, "candela"),
+    unit("L", 1e-3, VOLUME, true, "litre"),
+    unit("gal", 3.785411784e-3, VOLUME, false, "US gallon"),
+    unit("mph", 0.44704, SPEED, false, "mile per hour"),
+    unit(
+        "kn",
+        1852.0 / 3600.0,
+        SPEED,
+        false,
+        "knot, nautical mile per hour",
+    ),
+    unit("lbf", 4.4482216152605, FORCE, false, "pound-force"),
+    unit("bar", 1e5, PRESSURE, true, "bar"),
+    unit("atm", 101325.0, PRESSURE, false, "standard atmosphere"),
+    unit(
+        "psi",
+        6894.757293168361,
+        PRESSURE,
+        false,
+        "pound-force per square inch",
+    ),
+    unit("eV", 1.602176634e-19, ENERGY, true, "electronvolt"),
+    unit("cal", 4.184, ENERGY, true, "thermochemical calorie"),
+    unit("Wh", 3600.0, ENERGY, true, "watt hour"),
+    unit(
+        "hp",
+        745.6998715822702,
+        POWER,
+        false,
+        "mechanical horsepower",
+    ),
+    unit("ton", 907.18474, MASS, false, "short ton, 2000 pounds"),
+    unit("BTU", 1055.05585262, ENERGY, false, "British thermal unit"),
+    unit("dyn", 1e-5, FORCE, false, "dyne"),
+    unit("erg", 1e-7, ENERGY, false, "erg"),
+    unit("Ba", 0.1, PRESSURE, false, "barye"),
+    derived("Hz", &[("s", -1)], "hertz"),
+    derived("N", FORCE, "newton"),
+    derived("Pa", PRESSURE, "pascal"),
+    derived("J", ENERGY, "joule"),
+    derived("W", POWER, "watt"),
+    derived("C", &[("s", 1), ("A", 1)], "coulomb"),
+    derived("V", &[("kg", 1), ("m", 2), ("s", -3), ("A", -1)], "volt"),
+    derived("ohm", &[("kg", 1), ("m", 2), ("s", -3), ("A", -2)], "ohm"),
+    derived("F", &[("kg", -1), ("m", -2), ("s", 4), ("A", 2)], "farad"),
+    derived("T", &[("kg", 1), ("s", -2), ("A", -1)], "tesla"),
+];
+
+pub const PREFIXES: &[(&str, f64)] = &[
+    ("Y", 1e24),
+    ("Z", 1e21),
+    ("E", 1e18),
+    ("P", 1e15),
+    ("T", 1e12),
+    ("G", 1e9),
+    ("M", 1e6),
+    ("k", 1e3),
+    ("h", 1e2),
+    ("da", 1e1),
+    ("d", 1e-1),
+    ("c", 1e-2),
+    ("m", 1e-3),
+    ("u", 1e-6),
+    ("n", 1e-9),
+    ("p", 1e-12),
+    ("f", 1e-15),
+    ("a", 1e-18),
+];
+
+/// Looks up a unit by name, which can start with an SI prefix if the unit
+/// allows it. Names without a prefix take precedence, e.g. `min` is a
+/// minute rather than a milli-inch.
+pub fn lookup(name: &str) -> Option<Unit> {
+    let find = |name: &str| UNITS.iter().find(|u| u.name == name);
+    if let Some(u) = find(name) {
+        return Some(Unit {
+            name: name.to_string(),
+            factor: u.factor,
+            dimension: Dimension::new(u.dimension),
+            percent: false,
+        });
+    }
+    PREFIXES.iter().find_map(|(prefix, factor)| {
+        let u = find(name.strip_prefix(prefix)?).filter(|u| u.prefixes)?;
+        Some(Unit {
+            name: name.to_string(),
+            factor: factor * u.factor,
+            dimension: Dimension::new(u.dimension),
+            percent: false,
+        })
+    })
+}
+
+/// The built-in units together with the ones defined in a session and the
+/// currencies of the exchange rates file
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Units {
+    /// in the order they were defined, as later ones can build on earlier
+    /// ones
+    custom: Vec<Unit>,
+    rates: Option<Rates>,
+}
+
+impl Units {
+    pub fn custom(&self) -> &[Unit] {
+        &self.custom
+    }
+
+    pub fn rates(&self) -> Option<&Rates> {
+        self.rates.as_ref()
+    }
+
+    pub fn set_rates(&mut self, rates: Option<Rates>) {
+        self.rates = rates;
+    }
+
+    /// Looks up a unit by name like `lookup`, including the units defined
+    /// in the session, which can have SI prefixes too
+    pub fn lookup(&self, name: &str) -> Option<Unit> {
+        self.lookup_physical(name)
+            .or_else(|| self.rates.as_ref()?.lookup(name))
+    }
+
+    /// Looks up a built-in or defined unit, leaving out the currencies
+    pub fn lookup_physical(&self, name: &str) -> Option<Unit> {
+        if let Some(u) = self.custom.iter().find(|u| u.name == name) {
+            return Some(u.clone());
+        }
+        lookup(name).or_else(|| {
+            PREFIXES.iter().find_map(|(prefix, factor)| {
+                let base = name.strip_prefix(prefix)?;
+                let u = self.custom.iter().find(|u| u.name == base)?;
+                Some(Unit {
+                    name: name.to_string(),
+                    factor: factor * u.factor,
+                    dimension: u.dimension.clone(),
+                    percent: false,
+                })
+            })
+        })
+    }
+
+    /// Resolves a unit written as names with exponents, e.g. km/h as
+    /// `[("km", 1), ("h", -1)]`
+    pub fn resolve(&self, factors: &[(String, i32)]) -> Result<Unit, String> {
+        let mut unit = Unit {
+            name: String::new(),
+            factor: 1.0,
+            dimension: Dimension::default(),
+            percent: false,
+        };
+        let names: Vec<(&str, i32)> = factors.iter().map(|(n, e)| (n.as_str(), *e)).collect();
+        unit.name = compose(&names);
+        for (name, exponent) in factors {
+            let u = self.lookup(name).ok_or(format!("{name} is not a unit"))?;
+            unit.factor *= u.factor.powi(*exponent);
+            unit.dimension = u
+                .dimension
+                .powi(*exponent)
+                .and_then(|d| unit.dimension.mul(&d))
+                .map_err(|e| format!("Cannot use the unit {}, {e}", unit.name))?;
+        }
+        Ok(unit)
+    }
+
+    /// Defines the unit `name` as `value`, or as a new base unit that
+    /// cannot be converted to any other unit when there is no value
+    pub fn define(&mut self, name: &str, value: Option<&Quantity>) -> Result<Unit, String> {
+        // a defined unit can be redefined, but not hide a built-in one, a
+        // currency or a prefixed form of either
+        if lookup(name).is_some() {
+            return Err(format!("{name} is already a built-in unit"));
+        }
+        if !self.custom.iter().any(|u| u.name == name) && self.lookup(name).is_some() {
+            return Err(format!("{name} is already a unit"));
+        }
+        let unit = match value {
+            Some(q) => Unit {
+                name: name.to_string(),
+                factor: q.si_value(),
+                dimension: q.unit.dimension.clone(),
+                percent: false,
+            },
+            None => Unit {
+                name: name.to_string(),
+                factor: 1.0,
+                dimension: Dimension::new(&[(name, 1)]),
+                percent: false,
+            },
+        };
+        match self.custom.iter_mut().find(|u| u.name == name) {
+            Some(u) => *u = unit.clone(),
+            None => self.custom.push(unit.clone()),
+        }
+        Ok(unit)
+    }
+}
+
+/// The units results are shown in
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum UnitSystem {
+    /// in the units the calculation produced
+    #[default]
+    AsEntered,
+    Si,
+    Imperial,
+    Cgs,
+}
+
+impl UnitSystem {
+    /// The units a result can be shown in, dimensions without one fall
+    /// back to SI
+    fn candidates(self) -> Vec<String> {
+        let time = ["ns", "us", "ms", "s", "min", "h", "d", "yr"];
+        let mut units: Vec<&str> = match self {
+            UnitSystem::AsEntered => vec![],
+            UnitSystem::Si => vec![
+                "nm", "um", "mm", "m", "km", "mg", "g", "kg", "t", "m^2", "km^2", "mL", "L", "m^3",
+                "m/s", "km/h", "m/s^2",
+            ],
+            UnitSystem::Imperial => vec![
+                "inch", "ft", "mi", "oz", "lb", "ton", "inch^2", "ft^2", "mi^2", "inch^3", "ft^3",
+                "gal", "ft/s", "mph", "ft/s^2", "lbf", "psi", "BTU", "hp",
+            ],
+            UnitSystem::Cgs => vec![
+                "um", "mm", "cm", "km", "mg", "g", "kg", "cm^2", "cm^3", "cm/s", "cm/s^2", "dyn",
+                "erg", "erg/s", "Ba",
+            ],
+        };
+        units.extend(time);
+        let mut units: Vec<String> = units.into_iter().map(String::from).collect();
+        if self == UnitSystem::Si {
+            for u in UNITS.iter().filter(|u| u.derived) {
+                units.extend(["m", "", "k", "M", "G"].map(|p| format!("{p}{}", u.name)));
+            }
+        }
+        units
+    }
+}
+
+impl std::fmt::Display for UnitSystem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            UnitSystem::AsEntered => "none",
+            UnitSystem::Si => "si",
+            UnitSystem::Imperial => "imperial",
+            UnitSystem::Cgs => "cgs",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn looks_up_prefixed_units() {
+        let km = lookup("km").unwrap();
+        assert_eq!(km.factor, 1000.0);
+        assert_eq!(km.dimension, Dimension::new(&[("m", 1)]));
+        assert_eq!(lookup("kg").unwrap().factor, 1.0);
+        assert_eq!(lookup("min").unwrap().factor, 60.0);
+        assert_eq!(lookup("mL").unwrap().factor, 1e-6);
+        assert!(lookup("kmi").is_none());
+        assert!(lookup("x").is_none());
+
+        let speed = Units::default().resolve(&split("km/h")).unwrap();
+        assert_eq!(speed.name, "km/h");
+        assert_eq!(split("kg m^2/s^2/K").len(), 4);
+        assert_eq!(speed.dimension, Dimension::new(SPEED));
+        assert_eq!(Unit::si(Dimension::new(ENERGY)).name, "J");
+        assert_eq!(
+            Unit::si(Dimension::new(&[("m", 2), ("s", -1)])).name,
+            "m^2/s"
+        );
+        assert_eq!(Unit::si(Dimension::new(&[("m", -1)])).name, "m^-1");
+    }
+
+    #[test]
+    fn defines_units() {
+        let mut units = Units::default();
+        let furlong = Quantity {
+            value: 201.168,
+            unit: lookup("m").unwrap(),
+        };
+        units.define("furlong", Some(&furlong)).unwrap();
+        units.define("wafer", None).unwrap();
+        assert_eq!(units.lookup("furlong").unwrap().factor, 201.168);
+        assert_eq!(units.lookup("kwafer").unwrap().factor, 1000.0);
+        let per_hour = units.resolve(&split("wafer/h")).unwrap();
+        assert_eq!(
+            per_hour.dimension,
+            Dimension::new(&[("wafer", 1), ("s", -1)])
+        );
+        assert_eq!(Unit::si(per_hour.dimension).name, "wafer/s");
+        assert!(units.define("m", None).is_err());
+        assert!(units.define("km", None).is_err());
+        // a picotonne
+        assert!(units.define("pt", None).is_err());
+        assert!(units.define("kwafer", None).is_err());
+        units.define("pts", None).unwrap();
+        assert_eq!(units.lookup("pts").unwrap().factor, 1.0);
+        units.define("wafer", None).unwrap();
+        assert!(Units::default().lookup("furlong").is_none());
+    }
+
+    #[test]
+    fn picks_readable_units() {
+        let quantity = |value: f64, unit: &str| Quantity {
+            value,
+            unit: Units::default().resolve(&split(unit)).unwrap(),
+        };
+        let shown = |q: Quantity, system| q.in_system(system).to_string();
+        let mut units = Units::default();
+        units.define("pts", None).unwrap();
+        let velocity = Quantity {
+            value: 40.0,
+            unit: units.resolve(&split("pts/week")).unwrap(),
+        };
+        assert_eq!(shown(velocity, UnitSystem::Si), "40 pts/week");
+        assert_eq!(shown(quantity(2500.0, "m"), UnitSystem::Si), "2.5 km");
+        assert_eq!(shown(quantity(0.5, "kg"), UnitSystem::Si), "500 g");
+        assert_eq!(shown(quantity(3000.0, "N m"), UnitSystem::Si), "3 kJ");
+        assert_eq!(shown(quantity(90.0, "s"), UnitSystem::Si), "1.5 min");
+        assert_eq!(shown(quantity(0.0, "m"), UnitSystem::Si), "0 m");
+        assert_eq!(shown(quantity(3.0, "ft"), UnitSystem::AsEntered), "3 ft");
+        assert_eq!(shown(quantity(32.0, "oz"), UnitSystem::Imperial), "2 lb");
+        assert_eq!(shown(quantity(1.0, "K"), UnitSystem::Imperial), "1 K");
+        assert_eq!(shown(quantity(1.0, "J"), UnitSystem::Cgs), "10000000 erg");
+        assert_eq!(
+            shown(quantity(1.0, "kg m^2/s"), UnitSystem::Si),
+            "1 kg m^2/s"
+        );
+    }
+}
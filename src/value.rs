@@ -0,0 +1,370 @@
+use crate::{
+    dates::DateTime,
+    interval::Interval,
+    uncertainty::Uncertain,
+    units::{Dimension, Quantity, Unit},
+};
+
+/// The result of evaluating an expression
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Number(f64),
+    Quantity(Quantity),
+    List(Vec<Value>),
+    Date(DateTime),
+    Uncertain(Uncertain),
+    Interval(Interval),
+}
+
+impl Value {
+    pub fn as_number(&self) -> Result<f64, String> {
+        match self {
+            Value::Number(n) => Ok(*n),
+            Value::Quantity(q) if q.unit.dimension.is_empty() => Ok(q.si_value()),
+            Value::Interval(i) if i.is_point() => Ok(i.lo),
+            Value::Interval(_) => Err(format!("Expected a number, not the interval {}", self)),
+            _ => Err(format!("Expected a number, not {}", self)),
+        }
+    }
+
+    pub fn as_list(&self) -> Result<&[Value], String> {
+        match self {
+            Value::List(items) => Ok(items),
+            _ => Err(format!("Expected a list, not {}", self)),
+        }
+    }
+
+    /// A value in SI units, a plain number if it has no dimension
+    pub fn from_si(value: f64, dimension: Dimension) -> Value {
+        if dimension.is_empty() {
+            Value::Number(value)
+        } else {
+            Value::Quantity(Quantity {
+                value,
+                unit: Unit::si(dimension),
+            })
+        }
+    }
+
+    /// Scales every number with the linear `op`, quantities keep their unit
+    pub fn map(&self, op: fn(f64) -> f64) -> Value {
+        match self {
+            Value::Number(n) => Value::Number(op(*n)),
+            Value::Quantity(q) => Value::Quantity(Quantity {
+                value: op(q.value),
+                unit: q.unit.clone(),
+            }),
+            Value::List(items) => Value::List(items.iter().map(|i| i.map(op)).collect()),
+            Value::Date(_) => self.clone(),
+            Value::Uncertain(u) => {
+                Value::Uncertain(Uncertain::combine(op(u.value), &[(u, op(1.0) - op(0.0))]))
+            }
+            // negation is exact, other factors are rounded
+            Value::Interval(i) if op(1.0).abs() == 1.0 => {
+                let (a, b) = (op(i.lo), op(i.hi));
+                Value::Interval(Interval {
+                    lo: a.min(b),
+                    hi: a.max(b),
+                })
+            }
+            Value::Interval(i) => Value::Interval(i.mul(&Interval::around(op(1.0) - op(0.0)))),
+        }
+    }
+
+    /// Combines two values element by element. A number is combined with
+    /// every element of a list, lists must have the same length.
+    pub fn broadcast(
+        &self,
+        other: &Value,
+        op: fn(&Value, &Value) -> Result<Value, String>,
+    ) -> Result<Value, String> {
+        let items: Result<Vec<Value>, String> = match (self, other) {
+            (Value::List(a), Value::List(b)) => {
+                if a.len() != b.len() {
+                    return Err(format!(
+                        "Cannot combine lists of lengths {} and {}",
+                        a.len(),
+                        b.len()
+                    ));
+                }
+                a.iter().zip(b).map(|(a, b)| a.broadcast(b, op)).collect()
+            }
+            (Value::List(a), _) => a.iter().map(|a| a.broadcast(other, op)).collect(),
+            (_, Value::List(b)) => b.iter().map(|b| self.broadcast(b, op)).collect(),
+            _ => return op(self, other),
+        };
+        Ok(Value::List(items?))
+    }
+
+    /// A number, possibly uncertain
+    fn as_uncertain(&self) -> Result<Uncertain, String> {
+        match self {
+            Value::Uncertain(u) => Ok(u.clone()),
+            Value::Number(n) => Ok(Uncertain::exact(*n)),
+            _ => Err(format!("Uncertain values cannot be combined with {self}")),
+        }
+    }
+
+    /// A number as an interval, possibly without width
+    pub fn as_interval(&self) -> Result<Interval, String> {
+        match self {
+            Value::Interval(i) => Ok(*i),
+            Value::Number(_) | Value::Quantity(_) => self
+                .as_number()
+                .map(|n| Interval { lo: n, hi: n })
+                .map_err(|_| format!("Intervals cannot be combined with {self}")),
+            _ => Err(format!("Intervals cannot be combined with {self}")),
+        }
+    }
+
+    /// Applies an interval operation when either value is an interval
+    fn interval(
+        a: &Value,
+        b: &Value,
+        op: fn(&Interval, &Interval) -> Result<Interval, String>,
+    ) -> Option<Result<Value, String>> {
+        if !matches!((a, b), (Value::Interval(_), _) | (_, Value::Interval(_))) {
+            return None;
+        }
+        Some(
+            a.as_interval()
+                .and_then(|a| op(&a, &b.as_interval()?))
+                .map(Value::Interval),
+        )
+    }
+
+    /// Applies an operation with the partial derivatives `partials` when
+    /// either value is uncertain
+    fn uncertain(
+        a: &Value,
+        b: &Value,
+        op: fn(f64, f64) -> f64,
+        partials: fn(f64, f64) -> (f64, f64),
+    ) -> Option<Result<Value, String>> {
+        if !matches!((a, b), (Value::Uncertain(_), _) | (_, Value::Uncertain(_))) {
+            return None;
+        }
+        let (a, b) = match (a.as_uncertain(), b.as_uncertain()) {
+            (Ok(a), Ok(b)) => (a, b),
+            (Err(e), _) | (_, Err(e)) => return Some(Err(e)),
+        };
+        let (da, db) = partials(a.value, b.value);
+        let result = Uncertain::combine(op(a.value, b.value), &[(&a, da), (&b, db)]);
+        if result.uncertainty().is_nan() {
+            return Some(Err(format!(
+                "The uncertainty is not defined for {} and {}",
+                a.format(),
+                b.format()
+            )));
+        }
+        Some(Ok(Value::Uncertain(result)))
+    }
+
+    pub fn add(a: &Value, b: &Value) -> Result<Value, String> {
+        if let Some(result) = Self::interval(a, b, |x, y| Ok(x.add(y))) {
+            return result;
+        }
+        if let Some(result) = Self::uncertain(a, b, |x, y| x + y, |_, _| (1.0, 1.0)) {
+            return result;
+        }
+        match (a, b) {
+            (Value::Date(d), Value::Quantity(q)) | (Value::Quantity(q), Value::Date(d)) => {
+                Ok(Value::Date(d.add(q)?))
+            }
+            _ => Self::same_unit(a, b, "+", |x, y| x + y),
+        }
+    }
+
+    pub fn sub(a: &Value, b: &Value) -> Result<Value, String> {
+        if let Some(result) = Self::interval(a, b, |x, y| Ok(x.sub(y))) {
+            return result;
+        }
+        if let Some(result) = Self::uncertain(a, b, |x, y| x - y, |_, _| (1.0, -1.0)) {
+            return result;
+        }
+        match (a, b) {
+            (Value::Date(d), Value::Date(e)) => Ok(Value::Quantity(d.since(e))),
+            (Value::Date(d), Value::Quantity(q)) => {
+                let q = Quantity {
+                    value: -q.value,
+                    unit: q.unit.clone(),
+                };
+                Ok(Value::Date(d.add(&q)?))
+            }
+            _ => Self::same_unit(a, b, "-", |x, y| x - y),
+        }
+    }
+
+    pub fn rem(a: &Value, b: &Value) -> Result<Value, String> {
+        if matches!((a, b), (Value::Interval(_), _) | (_, Value::Interval(_))) {
+            // the remainder of exact numbers is exact
+            return match (a.as_number(), b.as_number()) {
+                (Ok(x), Ok(y)) => Ok(Value::Interval(Interval {
+                    lo: x % y,
+                    hi: x % y,
+                })),
+                _ => Err(format!("mod does not support intervals, in {a} mod {b}")),
+            };
+        }
+        let partials = |x: f64, y: f64| (1.0, -(x / y).trunc());
+        if let Some(result) = Self::uncertain(a, b, |x, y| x % y, partials) {
+            return result;
+        }
+        Self::same_unit(a, b, "mod", |x, y| x % y)
+    }
+
+    /// Operations on values of the same dimension. The result is in the
+    /// unit of the left one, a percentage and a number give a number.
+    fn same_unit(
+        a: &Value,
+        b: &Value,
+        symbol: &str,
+        op: fn(f64, f64) -> f64,
+    ) -> Result<Value, String> {
+        match (a, b) {
+            (Value::Quantity(q), Value::Number(_)) | (Value::Number(_), Value::Quantity(q))
+                if q.unit.dimension.is_empty() =>
+            {
+                Ok(Value::Number(op(a.as_number()?, b.as_number()?)))
+            }
+            (Value::Number(x), Value::Number(y)) => Ok(Value::Number(op(*x, *y))),
+            (Value::Quantity(q), Value::Quantity(r)) if q.unit.dimension == r.unit.dimension => {
+                let r = r.convert(&q.unit)?;
+                Ok(Value::Quantity(Quantity {
+                    value: op(q.value, r.value),
+                    unit: q.unit.clone(),
+                }))
+            }
+            _ => Err(format!("Incompatible units in {a} {symbol} {b}")),
+        }
+    }
+
+    /// A percentage as the plain number it stands for
+    fn plain(&self) -> Value {
+        match self {
+            Value::Quantity(q) if q.unit.dimension.is_empty() => Value::Number(q.si_value()),
+            _ => self.clone(),
+        }
+    }
+
+    pub fn mul(a: &Value, b: &Value) -> Result<Value, String> {
+        let (a, b) = (&a.plain(), &b.plain());
+        if let Some(result) = Self::interval(a, b, |x, y| Ok(x.mul(y))) {
+            return result;
+        }
+        if let Some(result) = Self::uncertain(a, b, |x, y| x * y, |x, y| (y, x)) {
+            return result;
+        }
+        Ok(match (a, b) {
+            (Value::Number(x), Value::Number(y)) => Value::Number(x * y),
+            (Value::Quantity(q), Value::Number(x)) | (Value::Number(x), Value::Quantity(q)) => {
+                Value::Quantity(Quantity {
+                    value: q.value * x,
+                    unit: q.unit.clone(),
+                })
+            }
+            (Value::Quantity(q), Value::Quantity(r)) => Value::from_si(
+                q.si_value() * r.si_value(),
+                q.unit
+                    .dimension
+                    .mul(&r.unit.dimension)
+                    .map_err(|e| format!("Cannot multiply {a} by {b}, {e}"))?,
+            ),
+            _ => return Err(format!("Cannot multiply {a} by {b}")),
+        })
+    }
+
+    pub fn div(a: &Value, b: &Value) -> Result<Value, String> {
+        let (a, b) = (&a.plain(), &b.plain());
+        if let Some(result) = Self::interval(a, b, Interval::div) {
+            return result;
+        }
+        let partials = |x: f64, y: f64| (1.0 / y, -x / (y * y));
+        if let Some(result) = Self::uncertain(a, b, |x, y| x / y, partials) {
+            return result;
+        }
+        Ok(match (a, b) {
+            (Value::Number(x), Value::Number(y)) => Value::Number(x / y),
+            (Value::Quantity(q), Value::Number(x)) => Value::Quantity(Quantity {
+                value: q.value / x,
+                unit: q.unit.clone(),
+            }),
+            (Value::Number(x), Value::Quantity(q)) => Value::from_si(
+                x / q.si_value(),
+                q.unit
+                    .dimension
+                    .powi(-1)
+                    .map_err(|e| format!("Cannot divide {a} by {b}, {e}"))?,
+            ),
+            (Value::Quantity(q), Value::Quantity(r)) => Value::from_si(
+                q.si_value() / r.si_value(),
+                r.unit
+                    .dimension
+                    .powi(-1)
+                    .and_then(|d| q.unit.dimension.mul(&d))
+                    .map_err(|e| format!("Cannot divide {a} by {b}, {e}"))?,
+            ),
+            _ => return Err(format!("Cannot divide {a} by {b}")),
+        })
+    }
+
+    pub fn pow(a: &Value, b: &Value) -> Result<Value, String> {
+        if let Some(result) = Self::interval(a, b, Interval::pow) {
+            return result;
+        }
+        let partials = |x: f64, y: f64| {
+            let dy = if x > 0.0 { x.powf(y) * x.ln() } else { 0.0 };
+            (y * x.powf(y - 1.0), dy)
+        };
+        if let Some(result) = Self::uncertain(a, b, f64::powf, partials) {
+            let result = result?;
+            if let Value::Uncertain(u) = &result {
+                if u.value.is_nan() {
+                    return Err(format!("{a}^{b} is not a real number"));
+                }
+            }
+            return Ok(result);
+        }
+        let Value::Number(exponent) = b else {
+            return Err(format!("The exponent {b} must be a plain number"));
+        };
+        let (base, dimension) = match a {
+            Value::Number(x) => (*x, Dimension::default()),
+            Value::Quantity(q) => (
+                q.si_value(),
+                q.unit
+                    .dimension
+                    .powf(*exponent)
+                    .map_err(|e| format!("Cannot raise {a} to the power of {exponent}, {e}"))?,
+            ),
+            _ => return Err(format!("Cannot raise {a} to a power")),
+        };
+        let value = base.powf(*exponent);
+        if value.is_nan() && !base.is_nan() && !exponent.is_nan() {
+            return Err(format!("{a}^{b} is not a real number"));
+        }
+        Ok(Value::from_si(value, dimension))
+    }
+}
+
+impl From<f64> for Value {
+    fn from(n: f64) -> Self {
+        Value::Number(n)
+    }
+}
+
+impl std::fmt::Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Number(n) => write!(f, "{}", n),
+            Value::Quantity(q) => write!(f, "{}", q),
+            Value::Date(d) => write!(f, "{}", d),
+            Value::Uncertain(u) => write!(f, "{}", u),
+            Value::Interval(i) => write!(f, "{}", i),
+            Value::List(items) => {
+                let items: Vec<String> = items.iter().map(|i| i.to_string()).collect();
+                write!(f, "[{}]", items.join(", "))
+            }
+        }
+    }
+}
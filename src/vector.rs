@@ -0,0 +1,108 @@
+//! Operations on lists of numbers, which are treated as vectors by the
+//! functions of the function registry
+
+use crate::value::Value;
+
+/// Longest list `range` creates
+const MAX_RANGE: f64 = 1_000_000.0;
+
+/// The numbers of a list, nested lists are rejected
+pub fn numbers(list: &[Value]) -> Result<Vec<f64>, String> {
+    list.iter().map(Value::as_number).collect()
+}
+
+/// The position of `index` in a list of length `len`, negative indices
+/// count from the end
+fn position(index: f64, len: usize) -> Result<Option<usize>, String> {
+    if index.fract() != 0.0 || !index.is_finite() {
+        return Err(format!("Index must be a whole number, not {index}"));
+    }
+    let position = if index < 0.0 {
+        len as f64 + index
+    } else {
+        index
+    };
+    if position < 0.0 || position >= len as f64 {
+        return Ok(None);
+    }
+    Ok(Some(position as usize))
+}
+
+pub fn index(list: &[Value], index: f64) -> Result<Value, String> {
+    match position(index, list.len())? {
+        Some(i) => Ok(list[i].clone()),
+        None => Err(format!(
+            "Index {index} is out of range for a list of length {}",
+            list.len()
+        )),
+    }
+}
+
+/// The elements from `start` up to but not including `end`. Like indices,
+/// bounds can be negative, and bounds past the ends of the list are clamped.
+pub fn slice(list: &[Value], start: Option<f64>, end: Option<f64>) -> Result<Value, String> {
+    let len = list.len();
+    let bound = |bound: Option<f64>, default: usize| -> Result<usize, String> {
+        let Some(bound) = bound else {
+            return Ok(default);
+        };
+        Ok(match position(bound, len)? {
+            Some(i) => i,
+            None if bound < 0.0 => 0,
+            None => len,
+        })
+    };
+    let start = bound(start, 0)?;
+    let end = bound(end, len)?.max(start);
+    Ok(Value::List(list[start..end].to_vec()))
+}
+
+/// The numbers from `start` up to but not including `stop`
+pub fn range(start: f64, stop: f64, step: f64) -> Result<Vec<f64>, String> {
+    if step == 0.0 || !step.is_finite() {
+        return Err(format!("range step must be a non-zero number, not {step}"));
+    }
+    let count = ((stop - start) / step).ceil().max(0.0);
+    if count.is_nan() || count > MAX_RANGE {
+        return Err(format!("range is limited to {MAX_RANGE} numbers"));
+    }
+    Ok((0..count as usize)
+        .map(|i| start + i as f64 * step)
+        .collect())
+}
+
+fn same_length(name: &str, a: &[f64], b: &[f64]) -> Result<(), String> {
+    if a.len() != b.len() {
+        return Err(format!(
+            "{name} requires lists of the same length, not {} and {}",
+            a.len(),
+            b.len()
+        ));
+    }
+    Ok(())
+}
+
+pub fn dot(a: &[f64], b: &[f64]) -> Result<f64, String> {
+    same_length("dot", a, b)?;
+    Ok(a.iter().zip(b).map(|(a, b)| a * b).sum())
+}
+
+pub fn cross(a: &[f64], b: &[f64]) -> Result<Vec<f64>, String> {
+    match (a, b) {
+        (&[a1, a2, a3], &[b1, b2, b3]) => Ok(vec![
+            a2 * b3 - a3 * b2,
+            a3 * b1 - a1 * b3,
+            a1 * b2 - a2 * b1,
+        ]),
+        _ => Err(format!(
+            "cross requires two lists of length 3, not {} and {}",
+            a.len(),
+            b.len()
+        )),
+    }
+}
+
+/// Euclidean length
+pub fn norm(v: &[f64]) -> f64 {
+    v.iter().map(|x| x * x).sum::<f64>().sqrt()
+}